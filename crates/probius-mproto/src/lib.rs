@@ -9,9 +9,21 @@ use mproto::{BaseLen, Compatible, Decode, DecodeCursor, DecodeError, DecodeResul
 #[cfg(any(feature = "std", feature = "alloc"))]
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct SinkHandshake {
+    pub protocol_version: u16,
+    pub features: u32,
+    pub expects_reply: bool,
     pub app_name: String,
     pub session_id_hi: u64,
     pub session_id_lo: u64,
+    pub is_replay: bool,
+    pub compression: FrameCompression,
+    pub hostname: String,
+    pub pid: u32,
+    pub executable: String,
+    pub app_version: String,
+    pub probius_version: String,
+    pub start_time_unix_nanos: u64,
+    pub attributes: Vec<ResourceAttribute>,
 }
 
 pub struct SinkHandshakeLazy<'a> {
@@ -21,36 +33,90 @@ pub struct SinkHandshakeLazy<'a> {
 
 pub struct SinkHandshakeGen<
     AppName: Encode + Compatible<String>,
+    Compression: Encode + Compatible<FrameCompression>,
+    Hostname: Encode + Compatible<String>,
+    Executable: Encode + Compatible<String>,
+    AppVersion: Encode + Compatible<String>,
+    ProbiusVersion: Encode + Compatible<String>,
+    Attributes: Encode + Compatible<Vec<ResourceAttribute>>,
 > {
+    pub protocol_version: u16,
+    pub features: u32,
+    pub expects_reply: bool,
     pub app_name: AppName,
     pub session_id_hi: u64,
     pub session_id_lo: u64,
+    pub is_replay: bool,
+    pub compression: Compression,
+    pub hostname: Hostname,
+    pub pid: u32,
+    pub executable: Executable,
+    pub app_version: AppVersion,
+    pub probius_version: ProbiusVersion,
+    pub start_time_unix_nanos: u64,
+    pub attributes: Attributes,
 }
 
 impl<
-    AppName: Encode + Compatible<String>
-> Compatible<SinkHandshake> for SinkHandshakeGen<AppName> { }
+    AppName: Encode + Compatible<String>,
+    Compression: Encode + Compatible<FrameCompression>,
+    Hostname: Encode + Compatible<String>,
+    Executable: Encode + Compatible<String>,
+    AppVersion: Encode + Compatible<String>,
+    ProbiusVersion: Encode + Compatible<String>,
+    Attributes: Encode + Compatible<Vec<ResourceAttribute>>
+> Compatible<SinkHandshake> for SinkHandshakeGen<AppName, Compression, Hostname, Executable, AppVersion, ProbiusVersion, Attributes> { }
 impl<
-    AppName: Encode + Compatible<String>
-> Compatible<SinkHandshakeGen<AppName>> for SinkHandshake { }
+    AppName: Encode + Compatible<String>,
+    Compression: Encode + Compatible<FrameCompression>,
+    Hostname: Encode + Compatible<String>,
+    Executable: Encode + Compatible<String>,
+    AppVersion: Encode + Compatible<String>,
+    ProbiusVersion: Encode + Compatible<String>,
+    Attributes: Encode + Compatible<Vec<ResourceAttribute>>
+> Compatible<SinkHandshakeGen<AppName, Compression, Hostname, Executable, AppVersion, ProbiusVersion, Attributes>> for SinkHandshake { }
 
 impl<
     AppName: Encode + Compatible<String>,
-> BaseLen for SinkHandshakeGen<AppName> {
-    const BASE_LEN: usize = 16 + AppName::BASE_LEN;
+    Compression: Encode + Compatible<FrameCompression>,
+    Hostname: Encode + Compatible<String>,
+    Executable: Encode + Compatible<String>,
+    AppVersion: Encode + Compatible<String>,
+    ProbiusVersion: Encode + Compatible<String>,
+    Attributes: Encode + Compatible<Vec<ResourceAttribute>>,
+> BaseLen for SinkHandshakeGen<AppName, Compression, Hostname, Executable, AppVersion, ProbiusVersion, Attributes> {
+    const BASE_LEN: usize = 36 + AppName::BASE_LEN + Compression::BASE_LEN + Hostname::BASE_LEN + Executable::BASE_LEN + AppVersion::BASE_LEN + ProbiusVersion::BASE_LEN + Attributes::BASE_LEN;
 }
 
 impl<
     AppName: Encode + Compatible<String>,
-> Encode for SinkHandshakeGen<AppName> {
+    Compression: Encode + Compatible<FrameCompression>,
+    Hostname: Encode + Compatible<String>,
+    Executable: Encode + Compatible<String>,
+    AppVersion: Encode + Compatible<String>,
+    ProbiusVersion: Encode + Compatible<String>,
+    Attributes: Encode + Compatible<Vec<ResourceAttribute>>,
+> Encode for SinkHandshakeGen<AppName, Compression, Hostname, Executable, AppVersion, ProbiusVersion, Attributes> {
     fn scratch_len(&self) -> usize {
-        self.app_name.scratch_len() + self.session_id_hi.scratch_len() + self.session_id_lo.scratch_len()
+        self.protocol_version.scratch_len() + self.features.scratch_len() + self.expects_reply.scratch_len() + self.app_name.scratch_len() + self.session_id_hi.scratch_len() + self.session_id_lo.scratch_len() + self.is_replay.scratch_len() + self.compression.scratch_len() + self.hostname.scratch_len() + self.pid.scratch_len() + self.executable.scratch_len() + self.app_version.scratch_len() + self.probius_version.scratch_len() + self.start_time_unix_nanos.scratch_len() + self.attributes.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
+        self.protocol_version.encode(cursor);
+        self.features.encode(cursor);
+        self.expects_reply.encode(cursor);
         self.app_name.encode(cursor);
         self.session_id_hi.encode(cursor);
         self.session_id_lo.encode(cursor);
+        self.is_replay.encode(cursor);
+        self.compression.encode(cursor);
+        self.hostname.encode(cursor);
+        self.pid.encode(cursor);
+        self.executable.encode(cursor);
+        self.app_version.encode(cursor);
+        self.probius_version.encode(cursor);
+        self.start_time_unix_nanos.encode(cursor);
+        self.attributes.encode(cursor);
     }
 }
 
@@ -77,70 +143,190 @@ impl<'a> Compatible<SinkHandshake> for SinkHandshakeLazy<'a> { }
 
 impl<'a> SinkHandshakeLazy<'a> {
 
-    pub fn app_name(&self) -> DecodeResult<&'a str> {
+    pub fn protocol_version(&self) -> DecodeResult<u16> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
     }
 
+    pub fn features(&self) -> DecodeResult<u32> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 2))
+    }
+
+    pub fn expects_reply(&self) -> DecodeResult<bool> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 6))
+    }
+
+    pub fn app_name(&self) -> DecodeResult<&'a str> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 7))
+    }
+
     pub fn session_id_hi(&self) -> DecodeResult<u64> {
-        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8))
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 15))
     }
 
     pub fn session_id_lo(&self) -> DecodeResult<u64> {
-        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16))
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 23))
+    }
+
+    pub fn is_replay(&self) -> DecodeResult<bool> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 31))
+    }
+
+    pub fn compression(&self) -> DecodeResult<FrameCompressionLazy> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 32))
+    }
+
+    pub fn hostname(&self) -> DecodeResult<&'a str> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 33))
+    }
+
+    pub fn pid(&self) -> DecodeResult<u32> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 41))
+    }
+
+    pub fn executable(&self) -> DecodeResult<&'a str> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 45))
+    }
+
+    pub fn app_version(&self) -> DecodeResult<&'a str> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 53))
+    }
+
+    pub fn probius_version(&self) -> DecodeResult<&'a str> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 61))
+    }
+
+    pub fn start_time_unix_nanos(&self) -> DecodeResult<u64> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 69))
+    }
+
+    pub fn attributes(&self) -> DecodeResult<mproto::ListLazy<'a, ResourceAttribute>> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 77))
     }
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
 impl BaseLen for SinkHandshake {
-    const BASE_LEN: usize = 24;
+    const BASE_LEN: usize = 85 + max(max(0, 0), 0);
 }
 
 impl Encode for SinkHandshake {
     fn scratch_len(&self) -> usize {
-        self.app_name.scratch_len() + self.session_id_hi.scratch_len() + self.session_id_lo.scratch_len()
+        self.protocol_version.scratch_len() + self.features.scratch_len() + self.expects_reply.scratch_len() + self.app_name.scratch_len() + self.session_id_hi.scratch_len() + self.session_id_lo.scratch_len() + self.is_replay.scratch_len() + self.compression.scratch_len() + self.hostname.scratch_len() + self.pid.scratch_len() + self.executable.scratch_len() + self.app_version.scratch_len() + self.probius_version.scratch_len() + self.start_time_unix_nanos.scratch_len() + self.attributes.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
+        self.protocol_version.encode(cursor);
+        self.features.encode(cursor);
+        self.expects_reply.encode(cursor);
         self.app_name.encode(cursor);
         self.session_id_hi.encode(cursor);
         self.session_id_lo.encode(cursor);
+        self.is_replay.encode(cursor);
+        self.compression.encode(cursor);
+        self.hostname.encode(cursor);
+        self.pid.encode(cursor);
+        self.executable.encode(cursor);
+        self.app_version.encode(cursor);
+        self.probius_version.encode(cursor);
+        self.start_time_unix_nanos.encode(cursor);
+        self.attributes.encode(cursor);
     }
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
 impl<'a> Decode<'a> for SinkHandshake {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let protocol_version = Decode::decode(cursor)?;
+        let features = Decode::decode(cursor)?;
+        let expects_reply = Decode::decode(cursor)?;
         let app_name = Decode::decode(cursor)?;
         let session_id_hi = Decode::decode(cursor)?;
         let session_id_lo = Decode::decode(cursor)?;
+        let is_replay = Decode::decode(cursor)?;
+        let compression = Decode::decode(cursor)?;
+        let hostname = Decode::decode(cursor)?;
+        let pid = Decode::decode(cursor)?;
+        let executable = Decode::decode(cursor)?;
+        let app_version = Decode::decode(cursor)?;
+        let probius_version = Decode::decode(cursor)?;
+        let start_time_unix_nanos = Decode::decode(cursor)?;
+        let attributes = Decode::decode(cursor)?;
 
         Ok(SinkHandshake {
+            protocol_version,
+            features,
+            expects_reply,
             app_name,
             session_id_hi,
             session_id_lo,
+            is_replay,
+            compression,
+            hostname,
+            pid,
+            executable,
+            app_version,
+            probius_version,
+            start_time_unix_nanos,
+            attributes,
         })
     }
 }
 
 impl<'a> BaseLen for SinkHandshakeLazy<'a> {
-    const BASE_LEN: usize = 24;
+    const BASE_LEN: usize = 85 + max(max(0, 0), 0);
 }
 
 impl<'a> Encode for SinkHandshakeLazy<'a> {
     fn scratch_len(&self) -> usize {
-        let app_name: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let session_id_hi: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
-        let session_id_lo: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
-        app_name.scratch_len() + session_id_hi.scratch_len() + session_id_lo.scratch_len()
+        let protocol_version: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let features: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 2)).unwrap();
+        let expects_reply: bool = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 6)).unwrap();
+        let app_name: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 7)).unwrap();
+        let session_id_hi: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 15)).unwrap();
+        let session_id_lo: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 23)).unwrap();
+        let is_replay: bool = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 31)).unwrap();
+        let compression: FrameCompressionLazy = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 32)).unwrap();
+        let hostname: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 33)).unwrap();
+        let pid: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 41)).unwrap();
+        let executable: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 45)).unwrap();
+        let app_version: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 53)).unwrap();
+        let probius_version: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 61)).unwrap();
+        let start_time_unix_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 69)).unwrap();
+        let attributes: mproto::ListLazy<'a, ResourceAttribute> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 77)).unwrap();
+        protocol_version.scratch_len() + features.scratch_len() + expects_reply.scratch_len() + app_name.scratch_len() + session_id_hi.scratch_len() + session_id_lo.scratch_len() + is_replay.scratch_len() + compression.scratch_len() + hostname.scratch_len() + pid.scratch_len() + executable.scratch_len() + app_version.scratch_len() + probius_version.scratch_len() + start_time_unix_nanos.scratch_len() + attributes.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        let app_name: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let session_id_hi: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
-        let session_id_lo: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
+        let protocol_version: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let features: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 2)).unwrap();
+        let expects_reply: bool = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 6)).unwrap();
+        let app_name: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 7)).unwrap();
+        let session_id_hi: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 15)).unwrap();
+        let session_id_lo: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 23)).unwrap();
+        let is_replay: bool = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 31)).unwrap();
+        let compression: FrameCompressionLazy = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 32)).unwrap();
+        let hostname: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 33)).unwrap();
+        let pid: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 41)).unwrap();
+        let executable: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 45)).unwrap();
+        let app_version: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 53)).unwrap();
+        let probius_version: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 61)).unwrap();
+        let start_time_unix_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 69)).unwrap();
+        let attributes: mproto::ListLazy<'a, ResourceAttribute> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 77)).unwrap();
+        protocol_version.encode(cursor);
+        features.encode(cursor);
+        expects_reply.encode(cursor);
         app_name.encode(cursor);
         session_id_hi.encode(cursor);
         session_id_lo.encode(cursor);
+        is_replay.encode(cursor);
+        compression.encode(cursor);
+        hostname.encode(cursor);
+        pid.encode(cursor);
+        executable.encode(cursor);
+        app_version.encode(cursor);
+        probius_version.encode(cursor);
+        start_time_unix_nanos.encode(cursor);
+        attributes.encode(cursor);
     }
 }
 
@@ -185,129 +371,288 @@ impl<'a> core::fmt::Debug for SinkHandshakeLazy<'a> {
 
 impl<'a> PartialEq for SinkHandshakeLazy<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.app_name().unwrap() == other.app_name().unwrap()
-            && self.session_id_hi().unwrap() == other.session_id_hi().unwrap()&& self.session_id_lo().unwrap() == other.session_id_lo().unwrap()
+        self.protocol_version().unwrap() == other.protocol_version().unwrap()
+            && self.features().unwrap() == other.features().unwrap()&& self.expects_reply().unwrap() == other.expects_reply().unwrap()&& self.app_name().unwrap() == other.app_name().unwrap()&& self.session_id_hi().unwrap() == other.session_id_hi().unwrap()&& self.session_id_lo().unwrap() == other.session_id_lo().unwrap()&& self.is_replay().unwrap() == other.is_replay().unwrap()&& self.compression().unwrap() == other.compression().unwrap()&& self.hostname().unwrap() == other.hostname().unwrap()&& self.pid().unwrap() == other.pid().unwrap()&& self.executable().unwrap() == other.executable().unwrap()&& self.app_version().unwrap() == other.app_version().unwrap()&& self.probius_version().unwrap() == other.probius_version().unwrap()&& self.start_time_unix_nanos().unwrap() == other.start_time_unix_nanos().unwrap()&& self.attributes().unwrap() == other.attributes().unwrap()
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub struct SourceId {
-    pub source: u64,
+pub enum FrameCompression {
+    Uncompressed,
+    Lz,
 }
 
-pub struct SourceIdLazy<'a> {
+#[derive(Clone)]
+pub enum FrameCompressionLazy {
+    Uncompressed,
+    Lz,
+}
+
+impl Compatible<FrameCompressionLazy> for FrameCompressionLazy { }
+impl Compatible<FrameCompressionLazy> for FrameCompression { }
+impl Compatible<FrameCompression> for FrameCompressionLazy { }
+impl Compatible<FrameCompression> for FrameCompression { }
+
+impl Owned for FrameCompression {
+    type Lazy<'a> = FrameCompressionLazy;
+
+    fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
+        TryFrom::try_from(lazy)
+    }
+}
+
+impl<'a> Lazy<'a> for FrameCompressionLazy {
+    type Owned = FrameCompression;
+}
+
+impl BaseLen for FrameCompression {
+    const BASE_LEN: usize = 1 + max(max(0, 0), 0);
+}
+
+impl Encode for FrameCompression {
+    fn scratch_len(&self) -> usize {
+        match self {
+            FrameCompression::Uncompressed => 0,
+            FrameCompression::Lz => 0,
+        }
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        match self {
+            FrameCompression::Uncompressed => {
+                cursor.base(1)[0] = 0;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            FrameCompression::Lz => {
+                cursor.base(1)[0] = 1;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+        }
+    }
+}
+
+impl<'a> Decode<'a> for FrameCompression {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let variant = cursor.base(1)[0];
+        match variant {
+            0 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(FrameCompression::Uncompressed)
+            }
+            1 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(FrameCompression::Lz)
+            }
+            _ => { Err(DecodeError) }
+        }
+    }
+}
+
+impl BaseLen for FrameCompressionLazy {
+    const BASE_LEN: usize = 1 + max(max(0, 0), 0);
+}
+
+impl Encode for FrameCompressionLazy {
+    fn scratch_len(&self) -> usize {
+        match self {
+            FrameCompressionLazy::Uncompressed => 0,
+            FrameCompressionLazy::Lz => 0,
+        }
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        match self {
+            FrameCompressionLazy::Uncompressed => {
+                cursor.base(1)[0] = 0;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            FrameCompressionLazy::Lz => {
+                cursor.base(1)[0] = 1;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+        }
+    }
+}
+
+impl<'a> Decode<'a> for FrameCompressionLazy {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let variant = cursor.base(1)[0];
+        match variant {
+            0 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(FrameCompressionLazy::Uncompressed)
+            }
+            1 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(FrameCompressionLazy::Lz)
+            }
+            _ => { Err(DecodeError) }
+        }
+    }
+}
+
+impl TryFrom<FrameCompressionLazy> for FrameCompression {
+    type Error = DecodeError;
+
+    fn try_from(other: FrameCompressionLazy) -> Result<Self, Self::Error> {
+        match other {
+            FrameCompressionLazy::Uncompressed => Ok(FrameCompression::Uncompressed),
+            FrameCompressionLazy::Lz => Ok(FrameCompression::Lz),
+        }
+    }
+}
+
+impl Copy for FrameCompressionLazy { }
+
+impl core::fmt::Debug for FrameCompressionLazy {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FrameCompressionLazy")
+            .finish()
+    }
+}
+
+impl PartialEq for FrameCompressionLazy {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (FrameCompressionLazy::Uncompressed, FrameCompressionLazy::Uncompressed) => true,
+            (FrameCompressionLazy::Lz, FrameCompressionLazy::Lz) => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct SinkHandshakeReply {
+    pub protocol_version: u16,
+    pub features: u32,
+}
+
+pub struct SinkHandshakeReplyLazy<'a> {
     buffer: &'a [u8],
     offset: usize,
 }
 
-pub struct SourceIdGen<> {
-    pub source: u64,
+pub struct SinkHandshakeReplyGen<> {
+    pub protocol_version: u16,
+    pub features: u32,
 }
 
-impl<> Compatible<SourceId> for SourceIdGen<> { }
-impl<> Compatible<SourceIdGen<>> for SourceId { }
+impl<> Compatible<SinkHandshakeReply> for SinkHandshakeReplyGen<> { }
+impl<> Compatible<SinkHandshakeReplyGen<>> for SinkHandshakeReply { }
 
-impl<> BaseLen for SourceIdGen<> {
-    const BASE_LEN: usize = 8;
+impl<> BaseLen for SinkHandshakeReplyGen<> {
+    const BASE_LEN: usize = 6;
 }
 
-impl<> Encode for SourceIdGen<> {
+impl<> Encode for SinkHandshakeReplyGen<> {
     fn scratch_len(&self) -> usize {
-        self.source.scratch_len()
+        self.protocol_version.scratch_len() + self.features.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.source.encode(cursor);
+        self.protocol_version.encode(cursor);
+        self.features.encode(cursor);
     }
 }
 
-impl Owned for SourceId {
-    type Lazy<'a> = SourceIdLazy<'a>;
+impl Owned for SinkHandshakeReply {
+    type Lazy<'a> = SinkHandshakeReplyLazy<'a>;
 
     fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
         TryFrom::try_from(lazy)
     }
 }
 
-impl<'a> Lazy<'a> for SourceIdLazy<'a> {
-    type Owned = SourceId;
+impl<'a> Lazy<'a> for SinkHandshakeReplyLazy<'a> {
+    type Owned = SinkHandshakeReply;
 }
 
-impl<'a> Compatible<SourceIdLazy<'a>> for SourceIdLazy<'a> { }
-impl<'a> Compatible<SourceIdLazy<'a>> for SourceId { }
-impl Compatible<SourceId> for SourceId { }
-impl<'a> Compatible<SourceId> for SourceIdLazy<'a> { }
+impl<'a> Compatible<SinkHandshakeReplyLazy<'a>> for SinkHandshakeReplyLazy<'a> { }
+impl<'a> Compatible<SinkHandshakeReplyLazy<'a>> for SinkHandshakeReply { }
+impl Compatible<SinkHandshakeReply> for SinkHandshakeReply { }
+impl<'a> Compatible<SinkHandshakeReply> for SinkHandshakeReplyLazy<'a> { }
 
-impl<'a> SourceIdLazy<'a> {
+impl<'a> SinkHandshakeReplyLazy<'a> {
 
-    pub fn source(&self) -> DecodeResult<u64> {
+    pub fn protocol_version(&self) -> DecodeResult<u16> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
     }
+
+    pub fn features(&self) -> DecodeResult<u32> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 2))
+    }
 }
 
-impl BaseLen for SourceId {
-    const BASE_LEN: usize = 8;
+impl BaseLen for SinkHandshakeReply {
+    const BASE_LEN: usize = 6;
 }
 
-impl Encode for SourceId {
+impl Encode for SinkHandshakeReply {
     fn scratch_len(&self) -> usize {
-        self.source.scratch_len()
+        self.protocol_version.scratch_len() + self.features.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.source.encode(cursor);
+        self.protocol_version.encode(cursor);
+        self.features.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for SourceId {
+impl<'a> Decode<'a> for SinkHandshakeReply {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
-        let source = Decode::decode(cursor)?;
+        let protocol_version = Decode::decode(cursor)?;
+        let features = Decode::decode(cursor)?;
 
-        Ok(SourceId {
-            source,
+        Ok(SinkHandshakeReply {
+            protocol_version,
+            features,
         })
     }
 }
 
-impl<'a> BaseLen for SourceIdLazy<'a> {
-    const BASE_LEN: usize = 8;
+impl<'a> BaseLen for SinkHandshakeReplyLazy<'a> {
+    const BASE_LEN: usize = 6;
 }
 
-impl<'a> Encode for SourceIdLazy<'a> {
+impl<'a> Encode for SinkHandshakeReplyLazy<'a> {
     fn scratch_len(&self) -> usize {
-        let source: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        source.scratch_len()
+        let protocol_version: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let features: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 2)).unwrap();
+        protocol_version.scratch_len() + features.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        let source: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        source.encode(cursor);
+        let protocol_version: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let features: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 2)).unwrap();
+        protocol_version.encode(cursor);
+        features.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for SourceIdLazy<'a> {
+impl<'a> Decode<'a> for SinkHandshakeReplyLazy<'a> {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
         let offset = cursor.offset();
         cursor.advance(Self::BASE_LEN);
-        Ok(SourceIdLazy {
+        Ok(SinkHandshakeReplyLazy {
             buffer: cursor.buffer(),
             offset,
         })
     }
 }
 
-impl<'a> TryFrom<SourceIdLazy<'a>> for SourceId {
+impl<'a> TryFrom<SinkHandshakeReplyLazy<'a>> for SinkHandshakeReply {
     type Error = DecodeError;
 
-    fn try_from(other: SourceIdLazy<'a>) -> Result<Self, Self::Error> {
+    fn try_from(other: SinkHandshakeReplyLazy<'a>) -> Result<Self, Self::Error> {
         let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
         Decode::decode(&cursor)
     }
 }
 
-impl<'a> Copy for SourceIdLazy<'a> { }
+impl<'a> Copy for SinkHandshakeReplyLazy<'a> { }
 
-impl<'a> Clone for SourceIdLazy<'a> {
+impl<'a> Clone for SinkHandshakeReplyLazy<'a> {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer,
@@ -316,160 +661,174 @@ impl<'a> Clone for SourceIdLazy<'a> {
     }
 }
 
-impl<'a> core::fmt::Debug for SourceIdLazy<'a> {
+impl<'a> core::fmt::Debug for SinkHandshakeReplyLazy<'a> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("SourceIdLazy")
+        f.debug_struct("SinkHandshakeReplyLazy")
             .finish()
     }
 }
 
-impl<'a> PartialEq for SourceIdLazy<'a> {
+impl<'a> PartialEq for SinkHandshakeReplyLazy<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.source().unwrap() == other.source().unwrap()
+        self.protocol_version().unwrap() == other.protocol_version().unwrap()
+            && self.features().unwrap() == other.features().unwrap()
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub struct GlobalSourceId {
-    pub session: u64,
-    pub source: SourceId,
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct ResourceAttribute {
+    pub key: String,
+    pub value: String,
 }
 
-pub struct GlobalSourceIdLazy<'a> {
+pub struct ResourceAttributeLazy<'a> {
     buffer: &'a [u8],
     offset: usize,
 }
 
-pub struct GlobalSourceIdGen<
-    Source: Encode + Compatible<SourceId>,
+pub struct ResourceAttributeGen<
+    Key: Encode + Compatible<String>,
+    Value: Encode + Compatible<String>,
 > {
-    pub session: u64,
-    pub source: Source,
+    pub key: Key,
+    pub value: Value,
 }
 
 impl<
-    Source: Encode + Compatible<SourceId>
-> Compatible<GlobalSourceId> for GlobalSourceIdGen<Source> { }
+    Key: Encode + Compatible<String>,
+    Value: Encode + Compatible<String>
+> Compatible<ResourceAttribute> for ResourceAttributeGen<Key, Value> { }
 impl<
-    Source: Encode + Compatible<SourceId>
-> Compatible<GlobalSourceIdGen<Source>> for GlobalSourceId { }
+    Key: Encode + Compatible<String>,
+    Value: Encode + Compatible<String>
+> Compatible<ResourceAttributeGen<Key, Value>> for ResourceAttribute { }
 
 impl<
-    Source: Encode + Compatible<SourceId>,
-> BaseLen for GlobalSourceIdGen<Source> {
-    const BASE_LEN: usize = 8 + Source::BASE_LEN;
+    Key: Encode + Compatible<String>,
+    Value: Encode + Compatible<String>,
+> BaseLen for ResourceAttributeGen<Key, Value> {
+    const BASE_LEN: usize = 0 + Key::BASE_LEN + Value::BASE_LEN;
 }
 
 impl<
-    Source: Encode + Compatible<SourceId>,
-> Encode for GlobalSourceIdGen<Source> {
+    Key: Encode + Compatible<String>,
+    Value: Encode + Compatible<String>,
+> Encode for ResourceAttributeGen<Key, Value> {
     fn scratch_len(&self) -> usize {
-        self.session.scratch_len() + self.source.scratch_len()
+        self.key.scratch_len() + self.value.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.session.encode(cursor);
-        self.source.encode(cursor);
+        self.key.encode(cursor);
+        self.value.encode(cursor);
     }
 }
 
-impl Owned for GlobalSourceId {
-    type Lazy<'a> = GlobalSourceIdLazy<'a>;
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Owned for ResourceAttribute {
+    type Lazy<'a> = ResourceAttributeLazy<'a>;
 
     fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
         TryFrom::try_from(lazy)
     }
 }
 
-impl<'a> Lazy<'a> for GlobalSourceIdLazy<'a> {
-    type Owned = GlobalSourceId;
+impl<'a> Lazy<'a> for ResourceAttributeLazy<'a> {
+    type Owned = ResourceAttribute;
 }
 
-impl<'a> Compatible<GlobalSourceIdLazy<'a>> for GlobalSourceIdLazy<'a> { }
-impl<'a> Compatible<GlobalSourceIdLazy<'a>> for GlobalSourceId { }
-impl Compatible<GlobalSourceId> for GlobalSourceId { }
-impl<'a> Compatible<GlobalSourceId> for GlobalSourceIdLazy<'a> { }
+impl<'a> Compatible<ResourceAttributeLazy<'a>> for ResourceAttributeLazy<'a> { }
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> Compatible<ResourceAttributeLazy<'a>> for ResourceAttribute { }
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Compatible<ResourceAttribute> for ResourceAttribute { }
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> Compatible<ResourceAttribute> for ResourceAttributeLazy<'a> { }
 
-impl<'a> GlobalSourceIdLazy<'a> {
+impl<'a> ResourceAttributeLazy<'a> {
 
-    pub fn session(&self) -> DecodeResult<u64> {
+    pub fn key(&self) -> DecodeResult<&'a str> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
     }
 
-    pub fn source(&self) -> DecodeResult<SourceIdLazy<'a>> {
+    pub fn value(&self) -> DecodeResult<&'a str> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8))
     }
 }
 
-impl BaseLen for GlobalSourceId {
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl BaseLen for ResourceAttribute {
     const BASE_LEN: usize = 16;
 }
 
-impl Encode for GlobalSourceId {
+impl Encode for ResourceAttribute {
     fn scratch_len(&self) -> usize {
-        self.session.scratch_len() + self.source.scratch_len()
+        self.key.scratch_len() + self.value.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.session.encode(cursor);
-        self.source.encode(cursor);
+        self.key.encode(cursor);
+        self.value.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for GlobalSourceId {
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> Decode<'a> for ResourceAttribute {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
-        let session = Decode::decode(cursor)?;
-        let source = Decode::decode(cursor)?;
+        let key = Decode::decode(cursor)?;
+        let value = Decode::decode(cursor)?;
 
-        Ok(GlobalSourceId {
-            session,
-            source,
+        Ok(ResourceAttribute {
+            key,
+            value,
         })
     }
 }
 
-impl<'a> BaseLen for GlobalSourceIdLazy<'a> {
+impl<'a> BaseLen for ResourceAttributeLazy<'a> {
     const BASE_LEN: usize = 16;
 }
 
-impl<'a> Encode for GlobalSourceIdLazy<'a> {
+impl<'a> Encode for ResourceAttributeLazy<'a> {
     fn scratch_len(&self) -> usize {
-        let session: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let source: SourceIdLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
-        session.scratch_len() + source.scratch_len()
+        let key: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let value: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        key.scratch_len() + value.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        let session: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let source: SourceIdLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
-        session.encode(cursor);
-        source.encode(cursor);
+        let key: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let value: &'a str = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        key.encode(cursor);
+        value.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for GlobalSourceIdLazy<'a> {
+impl<'a> Decode<'a> for ResourceAttributeLazy<'a> {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
         let offset = cursor.offset();
         cursor.advance(Self::BASE_LEN);
-        Ok(GlobalSourceIdLazy {
+        Ok(ResourceAttributeLazy {
             buffer: cursor.buffer(),
             offset,
         })
     }
 }
 
-impl<'a> TryFrom<GlobalSourceIdLazy<'a>> for GlobalSourceId {
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> TryFrom<ResourceAttributeLazy<'a>> for ResourceAttribute {
     type Error = DecodeError;
 
-    fn try_from(other: GlobalSourceIdLazy<'a>) -> Result<Self, Self::Error> {
+    fn try_from(other: ResourceAttributeLazy<'a>) -> Result<Self, Self::Error> {
         let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
         Decode::decode(&cursor)
     }
 }
 
-impl<'a> Copy for GlobalSourceIdLazy<'a> { }
+impl<'a> Copy for ResourceAttributeLazy<'a> { }
 
-impl<'a> Clone for GlobalSourceIdLazy<'a> {
+impl<'a> Clone for ResourceAttributeLazy<'a> {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer,
@@ -478,161 +837,190 @@ impl<'a> Clone for GlobalSourceIdLazy<'a> {
     }
 }
 
-impl<'a> core::fmt::Debug for GlobalSourceIdLazy<'a> {
+impl<'a> core::fmt::Debug for ResourceAttributeLazy<'a> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("GlobalSourceIdLazy")
+        f.debug_struct("ResourceAttributeLazy")
             .finish()
     }
 }
 
-impl<'a> PartialEq for GlobalSourceIdLazy<'a> {
+impl<'a> PartialEq for ResourceAttributeLazy<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.session().unwrap() == other.session().unwrap()
-            && self.source().unwrap() == other.source().unwrap()
+        self.key().unwrap() == other.key().unwrap()
+            && self.value().unwrap() == other.value().unwrap()
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub struct TraceCallerId {
-    pub event_id: EventId,
-    pub op_index: u16,
+pub struct UdpDatagramHeader {
+    pub session_id_hi: u64,
+    pub session_id_lo: u64,
+    pub sequence: u64,
+    pub fragment_index: u16,
+    pub fragment_count: u16,
 }
 
-pub struct TraceCallerIdLazy<'a> {
+pub struct UdpDatagramHeaderLazy<'a> {
     buffer: &'a [u8],
     offset: usize,
 }
 
-pub struct TraceCallerIdGen<
-    TEventId: Encode + Compatible<EventId>,
-> {
-    pub event_id: TEventId,
-    pub op_index: u16,
+pub struct UdpDatagramHeaderGen<> {
+    pub session_id_hi: u64,
+    pub session_id_lo: u64,
+    pub sequence: u64,
+    pub fragment_index: u16,
+    pub fragment_count: u16,
 }
 
-impl<
-    TEventId: Encode + Compatible<EventId>
-> Compatible<TraceCallerId> for TraceCallerIdGen<TEventId> { }
-impl<
-    TEventId: Encode + Compatible<EventId>
-> Compatible<TraceCallerIdGen<TEventId>> for TraceCallerId { }
+impl<> Compatible<UdpDatagramHeader> for UdpDatagramHeaderGen<> { }
+impl<> Compatible<UdpDatagramHeaderGen<>> for UdpDatagramHeader { }
 
-impl<
-    TEventId: Encode + Compatible<EventId>,
-> BaseLen for TraceCallerIdGen<TEventId> {
-    const BASE_LEN: usize = 2 + TEventId::BASE_LEN;
+impl<> BaseLen for UdpDatagramHeaderGen<> {
+    const BASE_LEN: usize = 28;
 }
 
-impl<
-    TEventId: Encode + Compatible<EventId>,
-> Encode for TraceCallerIdGen<TEventId> {
+impl<> Encode for UdpDatagramHeaderGen<> {
     fn scratch_len(&self) -> usize {
-        self.event_id.scratch_len() + self.op_index.scratch_len()
+        self.session_id_hi.scratch_len() + self.session_id_lo.scratch_len() + self.sequence.scratch_len() + self.fragment_index.scratch_len() + self.fragment_count.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.event_id.encode(cursor);
-        self.op_index.encode(cursor);
+        self.session_id_hi.encode(cursor);
+        self.session_id_lo.encode(cursor);
+        self.sequence.encode(cursor);
+        self.fragment_index.encode(cursor);
+        self.fragment_count.encode(cursor);
     }
 }
 
-impl Owned for TraceCallerId {
-    type Lazy<'a> = TraceCallerIdLazy<'a>;
+impl Owned for UdpDatagramHeader {
+    type Lazy<'a> = UdpDatagramHeaderLazy<'a>;
 
     fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
         TryFrom::try_from(lazy)
     }
 }
 
-impl<'a> Lazy<'a> for TraceCallerIdLazy<'a> {
-    type Owned = TraceCallerId;
+impl<'a> Lazy<'a> for UdpDatagramHeaderLazy<'a> {
+    type Owned = UdpDatagramHeader;
 }
 
-impl<'a> Compatible<TraceCallerIdLazy<'a>> for TraceCallerIdLazy<'a> { }
-impl<'a> Compatible<TraceCallerIdLazy<'a>> for TraceCallerId { }
-impl Compatible<TraceCallerId> for TraceCallerId { }
-impl<'a> Compatible<TraceCallerId> for TraceCallerIdLazy<'a> { }
+impl<'a> Compatible<UdpDatagramHeaderLazy<'a>> for UdpDatagramHeaderLazy<'a> { }
+impl<'a> Compatible<UdpDatagramHeaderLazy<'a>> for UdpDatagramHeader { }
+impl Compatible<UdpDatagramHeader> for UdpDatagramHeader { }
+impl<'a> Compatible<UdpDatagramHeader> for UdpDatagramHeaderLazy<'a> { }
 
-impl<'a> TraceCallerIdLazy<'a> {
+impl<'a> UdpDatagramHeaderLazy<'a> {
 
-    pub fn event_id(&self) -> DecodeResult<EventIdLazy<'a>> {
+    pub fn session_id_hi(&self) -> DecodeResult<u64> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
     }
 
-    pub fn op_index(&self) -> DecodeResult<u16> {
-        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 18))
+    pub fn session_id_lo(&self) -> DecodeResult<u64> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8))
+    }
+
+    pub fn sequence(&self) -> DecodeResult<u64> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16))
+    }
+
+    pub fn fragment_index(&self) -> DecodeResult<u16> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24))
+    }
+
+    pub fn fragment_count(&self) -> DecodeResult<u16> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 26))
     }
 }
 
-impl BaseLen for TraceCallerId {
-    const BASE_LEN: usize = 20;
+impl BaseLen for UdpDatagramHeader {
+    const BASE_LEN: usize = 28;
 }
 
-impl Encode for TraceCallerId {
+impl Encode for UdpDatagramHeader {
     fn scratch_len(&self) -> usize {
-        self.event_id.scratch_len() + self.op_index.scratch_len()
+        self.session_id_hi.scratch_len() + self.session_id_lo.scratch_len() + self.sequence.scratch_len() + self.fragment_index.scratch_len() + self.fragment_count.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.event_id.encode(cursor);
-        self.op_index.encode(cursor);
+        self.session_id_hi.encode(cursor);
+        self.session_id_lo.encode(cursor);
+        self.sequence.encode(cursor);
+        self.fragment_index.encode(cursor);
+        self.fragment_count.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for TraceCallerId {
+impl<'a> Decode<'a> for UdpDatagramHeader {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
-        let event_id = Decode::decode(cursor)?;
-        let op_index = Decode::decode(cursor)?;
+        let session_id_hi = Decode::decode(cursor)?;
+        let session_id_lo = Decode::decode(cursor)?;
+        let sequence = Decode::decode(cursor)?;
+        let fragment_index = Decode::decode(cursor)?;
+        let fragment_count = Decode::decode(cursor)?;
 
-        Ok(TraceCallerId {
-            event_id,
-            op_index,
+        Ok(UdpDatagramHeader {
+            session_id_hi,
+            session_id_lo,
+            sequence,
+            fragment_index,
+            fragment_count,
         })
     }
 }
 
-impl<'a> BaseLen for TraceCallerIdLazy<'a> {
-    const BASE_LEN: usize = 20;
+impl<'a> BaseLen for UdpDatagramHeaderLazy<'a> {
+    const BASE_LEN: usize = 28;
 }
 
-impl<'a> Encode for TraceCallerIdLazy<'a> {
+impl<'a> Encode for UdpDatagramHeaderLazy<'a> {
     fn scratch_len(&self) -> usize {
-        let event_id: EventIdLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let op_index: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 18)).unwrap();
-        event_id.scratch_len() + op_index.scratch_len()
+        let session_id_hi: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let session_id_lo: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        let sequence: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
+        let fragment_index: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
+        let fragment_count: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 26)).unwrap();
+        session_id_hi.scratch_len() + session_id_lo.scratch_len() + sequence.scratch_len() + fragment_index.scratch_len() + fragment_count.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        let event_id: EventIdLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let op_index: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 18)).unwrap();
-        event_id.encode(cursor);
-        op_index.encode(cursor);
+        let session_id_hi: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let session_id_lo: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        let sequence: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
+        let fragment_index: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
+        let fragment_count: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 26)).unwrap();
+        session_id_hi.encode(cursor);
+        session_id_lo.encode(cursor);
+        sequence.encode(cursor);
+        fragment_index.encode(cursor);
+        fragment_count.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for TraceCallerIdLazy<'a> {
+impl<'a> Decode<'a> for UdpDatagramHeaderLazy<'a> {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
         let offset = cursor.offset();
         cursor.advance(Self::BASE_LEN);
-        Ok(TraceCallerIdLazy {
+        Ok(UdpDatagramHeaderLazy {
             buffer: cursor.buffer(),
             offset,
         })
     }
 }
 
-impl<'a> TryFrom<TraceCallerIdLazy<'a>> for TraceCallerId {
+impl<'a> TryFrom<UdpDatagramHeaderLazy<'a>> for UdpDatagramHeader {
     type Error = DecodeError;
 
-    fn try_from(other: TraceCallerIdLazy<'a>) -> Result<Self, Self::Error> {
+    fn try_from(other: UdpDatagramHeaderLazy<'a>) -> Result<Self, Self::Error> {
         let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
         Decode::decode(&cursor)
     }
 }
 
-impl<'a> Copy for TraceCallerIdLazy<'a> { }
+impl<'a> Copy for UdpDatagramHeaderLazy<'a> { }
 
-impl<'a> Clone for TraceCallerIdLazy<'a> {
+impl<'a> Clone for UdpDatagramHeaderLazy<'a> {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer,
@@ -641,138 +1029,138 @@ impl<'a> Clone for TraceCallerIdLazy<'a> {
     }
 }
 
-impl<'a> core::fmt::Debug for TraceCallerIdLazy<'a> {
+impl<'a> core::fmt::Debug for UdpDatagramHeaderLazy<'a> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("TraceCallerIdLazy")
+        f.debug_struct("UdpDatagramHeaderLazy")
             .finish()
     }
 }
 
-impl<'a> PartialEq for TraceCallerIdLazy<'a> {
+impl<'a> PartialEq for UdpDatagramHeaderLazy<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.event_id().unwrap() == other.event_id().unwrap()
-            && self.op_index().unwrap() == other.op_index().unwrap()
+        self.session_id_hi().unwrap() == other.session_id_hi().unwrap()
+            && self.session_id_lo().unwrap() == other.session_id_lo().unwrap()&& self.sequence().unwrap() == other.sequence().unwrap()&& self.fragment_index().unwrap() == other.fragment_index().unwrap()&& self.fragment_count().unwrap() == other.fragment_count().unwrap()
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub struct EventSeq {
-    pub seq: u16,
+pub struct SourceId {
+    pub source: u64,
 }
 
-pub struct EventSeqLazy<'a> {
+pub struct SourceIdLazy<'a> {
     buffer: &'a [u8],
     offset: usize,
 }
 
-pub struct EventSeqGen<> {
-    pub seq: u16,
+pub struct SourceIdGen<> {
+    pub source: u64,
 }
 
-impl<> Compatible<EventSeq> for EventSeqGen<> { }
-impl<> Compatible<EventSeqGen<>> for EventSeq { }
+impl<> Compatible<SourceId> for SourceIdGen<> { }
+impl<> Compatible<SourceIdGen<>> for SourceId { }
 
-impl<> BaseLen for EventSeqGen<> {
-    const BASE_LEN: usize = 2;
+impl<> BaseLen for SourceIdGen<> {
+    const BASE_LEN: usize = 8;
 }
 
-impl<> Encode for EventSeqGen<> {
+impl<> Encode for SourceIdGen<> {
     fn scratch_len(&self) -> usize {
-        self.seq.scratch_len()
+        self.source.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.seq.encode(cursor);
+        self.source.encode(cursor);
     }
 }
 
-impl Owned for EventSeq {
-    type Lazy<'a> = EventSeqLazy<'a>;
+impl Owned for SourceId {
+    type Lazy<'a> = SourceIdLazy<'a>;
 
     fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
         TryFrom::try_from(lazy)
     }
 }
 
-impl<'a> Lazy<'a> for EventSeqLazy<'a> {
-    type Owned = EventSeq;
+impl<'a> Lazy<'a> for SourceIdLazy<'a> {
+    type Owned = SourceId;
 }
 
-impl<'a> Compatible<EventSeqLazy<'a>> for EventSeqLazy<'a> { }
-impl<'a> Compatible<EventSeqLazy<'a>> for EventSeq { }
-impl Compatible<EventSeq> for EventSeq { }
-impl<'a> Compatible<EventSeq> for EventSeqLazy<'a> { }
+impl<'a> Compatible<SourceIdLazy<'a>> for SourceIdLazy<'a> { }
+impl<'a> Compatible<SourceIdLazy<'a>> for SourceId { }
+impl Compatible<SourceId> for SourceId { }
+impl<'a> Compatible<SourceId> for SourceIdLazy<'a> { }
 
-impl<'a> EventSeqLazy<'a> {
+impl<'a> SourceIdLazy<'a> {
 
-    pub fn seq(&self) -> DecodeResult<u16> {
+    pub fn source(&self) -> DecodeResult<u64> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
     }
 }
 
-impl BaseLen for EventSeq {
-    const BASE_LEN: usize = 2;
+impl BaseLen for SourceId {
+    const BASE_LEN: usize = 8;
 }
 
-impl Encode for EventSeq {
+impl Encode for SourceId {
     fn scratch_len(&self) -> usize {
-        self.seq.scratch_len()
+        self.source.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.seq.encode(cursor);
+        self.source.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for EventSeq {
+impl<'a> Decode<'a> for SourceId {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
-        let seq = Decode::decode(cursor)?;
+        let source = Decode::decode(cursor)?;
 
-        Ok(EventSeq {
-            seq,
+        Ok(SourceId {
+            source,
         })
     }
 }
 
-impl<'a> BaseLen for EventSeqLazy<'a> {
-    const BASE_LEN: usize = 2;
+impl<'a> BaseLen for SourceIdLazy<'a> {
+    const BASE_LEN: usize = 8;
 }
 
-impl<'a> Encode for EventSeqLazy<'a> {
+impl<'a> Encode for SourceIdLazy<'a> {
     fn scratch_len(&self) -> usize {
-        let seq: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        seq.scratch_len()
+        let source: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        source.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        let seq: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        seq.encode(cursor);
+        let source: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        source.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for EventSeqLazy<'a> {
+impl<'a> Decode<'a> for SourceIdLazy<'a> {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
         let offset = cursor.offset();
         cursor.advance(Self::BASE_LEN);
-        Ok(EventSeqLazy {
+        Ok(SourceIdLazy {
             buffer: cursor.buffer(),
             offset,
         })
     }
 }
 
-impl<'a> TryFrom<EventSeqLazy<'a>> for EventSeq {
+impl<'a> TryFrom<SourceIdLazy<'a>> for SourceId {
     type Error = DecodeError;
 
-    fn try_from(other: EventSeqLazy<'a>) -> Result<Self, Self::Error> {
+    fn try_from(other: SourceIdLazy<'a>) -> Result<Self, Self::Error> {
         let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
         Decode::decode(&cursor)
     }
 }
 
-impl<'a> Copy for EventSeqLazy<'a> { }
+impl<'a> Copy for SourceIdLazy<'a> { }
 
-impl<'a> Clone for EventSeqLazy<'a> {
+impl<'a> Clone for SourceIdLazy<'a> {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer,
@@ -781,108 +1169,573 @@ impl<'a> Clone for EventSeqLazy<'a> {
     }
 }
 
-impl<'a> core::fmt::Debug for EventSeqLazy<'a> {
+impl<'a> core::fmt::Debug for SourceIdLazy<'a> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("EventSeqLazy")
+        f.debug_struct("SourceIdLazy")
             .finish()
     }
 }
 
-impl<'a> PartialEq for EventSeqLazy<'a> {
+impl<'a> PartialEq for SourceIdLazy<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.seq().unwrap() == other.seq().unwrap()
+        self.source().unwrap() == other.source().unwrap()
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub struct EventId {
+pub struct GlobalSourceId {
+    pub session: u64,
     pub source: SourceId,
-    pub timestamp_nanos: u64,
-    pub seq: EventSeq,
 }
 
-pub struct EventIdLazy<'a> {
+pub struct GlobalSourceIdLazy<'a> {
     buffer: &'a [u8],
     offset: usize,
 }
 
-pub struct EventIdGen<
+pub struct GlobalSourceIdGen<
     Source: Encode + Compatible<SourceId>,
-    Seq: Encode + Compatible<EventSeq>,
 > {
+    pub session: u64,
     pub source: Source,
-    pub timestamp_nanos: u64,
-    pub seq: Seq,
 }
 
 impl<
-    Source: Encode + Compatible<SourceId>,
-    Seq: Encode + Compatible<EventSeq>
-> Compatible<EventId> for EventIdGen<Source, Seq> { }
+    Source: Encode + Compatible<SourceId>
+> Compatible<GlobalSourceId> for GlobalSourceIdGen<Source> { }
 impl<
-    Source: Encode + Compatible<SourceId>,
-    Seq: Encode + Compatible<EventSeq>
-> Compatible<EventIdGen<Source, Seq>> for EventId { }
+    Source: Encode + Compatible<SourceId>
+> Compatible<GlobalSourceIdGen<Source>> for GlobalSourceId { }
 
 impl<
     Source: Encode + Compatible<SourceId>,
-    Seq: Encode + Compatible<EventSeq>,
-> BaseLen for EventIdGen<Source, Seq> {
-    const BASE_LEN: usize = 8 + Source::BASE_LEN + Seq::BASE_LEN;
+> BaseLen for GlobalSourceIdGen<Source> {
+    const BASE_LEN: usize = 8 + Source::BASE_LEN;
 }
 
 impl<
     Source: Encode + Compatible<SourceId>,
-    Seq: Encode + Compatible<EventSeq>,
-> Encode for EventIdGen<Source, Seq> {
+> Encode for GlobalSourceIdGen<Source> {
     fn scratch_len(&self) -> usize {
-        self.source.scratch_len() + self.timestamp_nanos.scratch_len() + self.seq.scratch_len()
+        self.session.scratch_len() + self.source.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
+        self.session.encode(cursor);
         self.source.encode(cursor);
-        self.timestamp_nanos.encode(cursor);
-        self.seq.encode(cursor);
     }
 }
 
-impl Owned for EventId {
-    type Lazy<'a> = EventIdLazy<'a>;
+impl Owned for GlobalSourceId {
+    type Lazy<'a> = GlobalSourceIdLazy<'a>;
 
     fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
         TryFrom::try_from(lazy)
     }
 }
 
-impl<'a> Lazy<'a> for EventIdLazy<'a> {
-    type Owned = EventId;
+impl<'a> Lazy<'a> for GlobalSourceIdLazy<'a> {
+    type Owned = GlobalSourceId;
 }
 
-impl<'a> Compatible<EventIdLazy<'a>> for EventIdLazy<'a> { }
-impl<'a> Compatible<EventIdLazy<'a>> for EventId { }
-impl Compatible<EventId> for EventId { }
-impl<'a> Compatible<EventId> for EventIdLazy<'a> { }
+impl<'a> Compatible<GlobalSourceIdLazy<'a>> for GlobalSourceIdLazy<'a> { }
+impl<'a> Compatible<GlobalSourceIdLazy<'a>> for GlobalSourceId { }
+impl Compatible<GlobalSourceId> for GlobalSourceId { }
+impl<'a> Compatible<GlobalSourceId> for GlobalSourceIdLazy<'a> { }
 
-impl<'a> EventIdLazy<'a> {
+impl<'a> GlobalSourceIdLazy<'a> {
 
-    pub fn source(&self) -> DecodeResult<SourceIdLazy<'a>> {
+    pub fn session(&self) -> DecodeResult<u64> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
     }
 
-    pub fn timestamp_nanos(&self) -> DecodeResult<u64> {
+    pub fn source(&self) -> DecodeResult<SourceIdLazy<'a>> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8))
     }
-
-    pub fn seq(&self) -> DecodeResult<EventSeqLazy<'a>> {
-        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16))
-    }
 }
 
-impl BaseLen for EventId {
-    const BASE_LEN: usize = 18;
+impl BaseLen for GlobalSourceId {
+    const BASE_LEN: usize = 16;
 }
 
-impl Encode for EventId {
+impl Encode for GlobalSourceId {
+    fn scratch_len(&self) -> usize {
+        self.session.scratch_len() + self.source.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.session.encode(cursor);
+        self.source.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for GlobalSourceId {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let session = Decode::decode(cursor)?;
+        let source = Decode::decode(cursor)?;
+
+        Ok(GlobalSourceId {
+            session,
+            source,
+        })
+    }
+}
+
+impl<'a> BaseLen for GlobalSourceIdLazy<'a> {
+    const BASE_LEN: usize = 16;
+}
+
+impl<'a> Encode for GlobalSourceIdLazy<'a> {
+    fn scratch_len(&self) -> usize {
+        let session: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let source: SourceIdLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        session.scratch_len() + source.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        let session: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let source: SourceIdLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        session.encode(cursor);
+        source.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for GlobalSourceIdLazy<'a> {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let offset = cursor.offset();
+        cursor.advance(Self::BASE_LEN);
+        Ok(GlobalSourceIdLazy {
+            buffer: cursor.buffer(),
+            offset,
+        })
+    }
+}
+
+impl<'a> TryFrom<GlobalSourceIdLazy<'a>> for GlobalSourceId {
+    type Error = DecodeError;
+
+    fn try_from(other: GlobalSourceIdLazy<'a>) -> Result<Self, Self::Error> {
+        let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
+        Decode::decode(&cursor)
+    }
+}
+
+impl<'a> Copy for GlobalSourceIdLazy<'a> { }
+
+impl<'a> Clone for GlobalSourceIdLazy<'a> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer,
+            offset: self.offset,
+        }
+    }
+}
+
+impl<'a> core::fmt::Debug for GlobalSourceIdLazy<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GlobalSourceIdLazy")
+            .finish()
+    }
+}
+
+impl<'a> PartialEq for GlobalSourceIdLazy<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.session().unwrap() == other.session().unwrap()
+            && self.source().unwrap() == other.source().unwrap()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct TraceCallerId {
+    pub event_id: EventId,
+    pub op_index: u16,
+}
+
+pub struct TraceCallerIdLazy<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+}
+
+pub struct TraceCallerIdGen<
+    TEventId: Encode + Compatible<EventId>,
+> {
+    pub event_id: TEventId,
+    pub op_index: u16,
+}
+
+impl<
+    TEventId: Encode + Compatible<EventId>
+> Compatible<TraceCallerId> for TraceCallerIdGen<TEventId> { }
+impl<
+    TEventId: Encode + Compatible<EventId>
+> Compatible<TraceCallerIdGen<TEventId>> for TraceCallerId { }
+
+impl<
+    TEventId: Encode + Compatible<EventId>,
+> BaseLen for TraceCallerIdGen<TEventId> {
+    const BASE_LEN: usize = 2 + TEventId::BASE_LEN;
+}
+
+impl<
+    TEventId: Encode + Compatible<EventId>,
+> Encode for TraceCallerIdGen<TEventId> {
+    fn scratch_len(&self) -> usize {
+        self.event_id.scratch_len() + self.op_index.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.event_id.encode(cursor);
+        self.op_index.encode(cursor);
+    }
+}
+
+impl Owned for TraceCallerId {
+    type Lazy<'a> = TraceCallerIdLazy<'a>;
+
+    fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
+        TryFrom::try_from(lazy)
+    }
+}
+
+impl<'a> Lazy<'a> for TraceCallerIdLazy<'a> {
+    type Owned = TraceCallerId;
+}
+
+impl<'a> Compatible<TraceCallerIdLazy<'a>> for TraceCallerIdLazy<'a> { }
+impl<'a> Compatible<TraceCallerIdLazy<'a>> for TraceCallerId { }
+impl Compatible<TraceCallerId> for TraceCallerId { }
+impl<'a> Compatible<TraceCallerId> for TraceCallerIdLazy<'a> { }
+
+impl<'a> TraceCallerIdLazy<'a> {
+
+    pub fn event_id(&self) -> DecodeResult<EventIdLazy<'a>> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
+    }
+
+    pub fn op_index(&self) -> DecodeResult<u16> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 18))
+    }
+}
+
+impl BaseLen for TraceCallerId {
+    const BASE_LEN: usize = 20;
+}
+
+impl Encode for TraceCallerId {
+    fn scratch_len(&self) -> usize {
+        self.event_id.scratch_len() + self.op_index.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.event_id.encode(cursor);
+        self.op_index.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for TraceCallerId {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let event_id = Decode::decode(cursor)?;
+        let op_index = Decode::decode(cursor)?;
+
+        Ok(TraceCallerId {
+            event_id,
+            op_index,
+        })
+    }
+}
+
+impl<'a> BaseLen for TraceCallerIdLazy<'a> {
+    const BASE_LEN: usize = 20;
+}
+
+impl<'a> Encode for TraceCallerIdLazy<'a> {
+    fn scratch_len(&self) -> usize {
+        let event_id: EventIdLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let op_index: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 18)).unwrap();
+        event_id.scratch_len() + op_index.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        let event_id: EventIdLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let op_index: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 18)).unwrap();
+        event_id.encode(cursor);
+        op_index.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for TraceCallerIdLazy<'a> {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let offset = cursor.offset();
+        cursor.advance(Self::BASE_LEN);
+        Ok(TraceCallerIdLazy {
+            buffer: cursor.buffer(),
+            offset,
+        })
+    }
+}
+
+impl<'a> TryFrom<TraceCallerIdLazy<'a>> for TraceCallerId {
+    type Error = DecodeError;
+
+    fn try_from(other: TraceCallerIdLazy<'a>) -> Result<Self, Self::Error> {
+        let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
+        Decode::decode(&cursor)
+    }
+}
+
+impl<'a> Copy for TraceCallerIdLazy<'a> { }
+
+impl<'a> Clone for TraceCallerIdLazy<'a> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer,
+            offset: self.offset,
+        }
+    }
+}
+
+impl<'a> core::fmt::Debug for TraceCallerIdLazy<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TraceCallerIdLazy")
+            .finish()
+    }
+}
+
+impl<'a> PartialEq for TraceCallerIdLazy<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.event_id().unwrap() == other.event_id().unwrap()
+            && self.op_index().unwrap() == other.op_index().unwrap()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct EventSeq {
+    pub seq: u16,
+}
+
+pub struct EventSeqLazy<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+}
+
+pub struct EventSeqGen<> {
+    pub seq: u16,
+}
+
+impl<> Compatible<EventSeq> for EventSeqGen<> { }
+impl<> Compatible<EventSeqGen<>> for EventSeq { }
+
+impl<> BaseLen for EventSeqGen<> {
+    const BASE_LEN: usize = 2;
+}
+
+impl<> Encode for EventSeqGen<> {
+    fn scratch_len(&self) -> usize {
+        self.seq.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.seq.encode(cursor);
+    }
+}
+
+impl Owned for EventSeq {
+    type Lazy<'a> = EventSeqLazy<'a>;
+
+    fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
+        TryFrom::try_from(lazy)
+    }
+}
+
+impl<'a> Lazy<'a> for EventSeqLazy<'a> {
+    type Owned = EventSeq;
+}
+
+impl<'a> Compatible<EventSeqLazy<'a>> for EventSeqLazy<'a> { }
+impl<'a> Compatible<EventSeqLazy<'a>> for EventSeq { }
+impl Compatible<EventSeq> for EventSeq { }
+impl<'a> Compatible<EventSeq> for EventSeqLazy<'a> { }
+
+impl<'a> EventSeqLazy<'a> {
+
+    pub fn seq(&self) -> DecodeResult<u16> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
+    }
+}
+
+impl BaseLen for EventSeq {
+    const BASE_LEN: usize = 2;
+}
+
+impl Encode for EventSeq {
+    fn scratch_len(&self) -> usize {
+        self.seq.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.seq.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for EventSeq {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let seq = Decode::decode(cursor)?;
+
+        Ok(EventSeq {
+            seq,
+        })
+    }
+}
+
+impl<'a> BaseLen for EventSeqLazy<'a> {
+    const BASE_LEN: usize = 2;
+}
+
+impl<'a> Encode for EventSeqLazy<'a> {
+    fn scratch_len(&self) -> usize {
+        let seq: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        seq.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        let seq: u16 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        seq.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for EventSeqLazy<'a> {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let offset = cursor.offset();
+        cursor.advance(Self::BASE_LEN);
+        Ok(EventSeqLazy {
+            buffer: cursor.buffer(),
+            offset,
+        })
+    }
+}
+
+impl<'a> TryFrom<EventSeqLazy<'a>> for EventSeq {
+    type Error = DecodeError;
+
+    fn try_from(other: EventSeqLazy<'a>) -> Result<Self, Self::Error> {
+        let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
+        Decode::decode(&cursor)
+    }
+}
+
+impl<'a> Copy for EventSeqLazy<'a> { }
+
+impl<'a> Clone for EventSeqLazy<'a> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer,
+            offset: self.offset,
+        }
+    }
+}
+
+impl<'a> core::fmt::Debug for EventSeqLazy<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EventSeqLazy")
+            .finish()
+    }
+}
+
+impl<'a> PartialEq for EventSeqLazy<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.seq().unwrap() == other.seq().unwrap()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct EventId {
+    pub source: SourceId,
+    pub timestamp_nanos: u64,
+    pub seq: EventSeq,
+}
+
+pub struct EventIdLazy<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+}
+
+pub struct EventIdGen<
+    Source: Encode + Compatible<SourceId>,
+    Seq: Encode + Compatible<EventSeq>,
+> {
+    pub source: Source,
+    pub timestamp_nanos: u64,
+    pub seq: Seq,
+}
+
+impl<
+    Source: Encode + Compatible<SourceId>,
+    Seq: Encode + Compatible<EventSeq>
+> Compatible<EventId> for EventIdGen<Source, Seq> { }
+impl<
+    Source: Encode + Compatible<SourceId>,
+    Seq: Encode + Compatible<EventSeq>
+> Compatible<EventIdGen<Source, Seq>> for EventId { }
+
+impl<
+    Source: Encode + Compatible<SourceId>,
+    Seq: Encode + Compatible<EventSeq>,
+> BaseLen for EventIdGen<Source, Seq> {
+    const BASE_LEN: usize = 8 + Source::BASE_LEN + Seq::BASE_LEN;
+}
+
+impl<
+    Source: Encode + Compatible<SourceId>,
+    Seq: Encode + Compatible<EventSeq>,
+> Encode for EventIdGen<Source, Seq> {
+    fn scratch_len(&self) -> usize {
+        self.source.scratch_len() + self.timestamp_nanos.scratch_len() + self.seq.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.source.encode(cursor);
+        self.timestamp_nanos.encode(cursor);
+        self.seq.encode(cursor);
+    }
+}
+
+impl Owned for EventId {
+    type Lazy<'a> = EventIdLazy<'a>;
+
+    fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
+        TryFrom::try_from(lazy)
+    }
+}
+
+impl<'a> Lazy<'a> for EventIdLazy<'a> {
+    type Owned = EventId;
+}
+
+impl<'a> Compatible<EventIdLazy<'a>> for EventIdLazy<'a> { }
+impl<'a> Compatible<EventIdLazy<'a>> for EventId { }
+impl Compatible<EventId> for EventId { }
+impl<'a> Compatible<EventId> for EventIdLazy<'a> { }
+
+impl<'a> EventIdLazy<'a> {
+
+    pub fn source(&self) -> DecodeResult<SourceIdLazy<'a>> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
+    }
+
+    pub fn timestamp_nanos(&self) -> DecodeResult<u64> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8))
+    }
+
+    pub fn seq(&self) -> DecodeResult<EventSeqLazy<'a>> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16))
+    }
+}
+
+impl BaseLen for EventId {
+    const BASE_LEN: usize = 18;
+}
+
+impl Encode for EventId {
     fn scratch_len(&self) -> usize {
         self.source.scratch_len() + self.timestamp_nanos.scratch_len() + self.seq.scratch_len()
     }
@@ -1163,6 +2016,8 @@ pub enum EventKind {
     Trace,
     TraceAggregate,
     TraceAggregateDelta,
+    ClockAnchor,
+    Heartbeat,
 }
 
 #[derive(Clone)]
@@ -1172,6 +2027,8 @@ pub enum EventKindLazy {
     Trace,
     TraceAggregate,
     TraceAggregateDelta,
+    ClockAnchor,
+    Heartbeat,
 }
 
 impl Compatible<EventKindLazy> for EventKindLazy { }
@@ -1192,7 +2049,7 @@ impl<'a> Lazy<'a> for EventKindLazy {
 }
 
 impl BaseLen for EventKind {
-    const BASE_LEN: usize = 1 + max(max(max(max(max(0, 0), 0), 0), 0), 0);
+    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(0, 0), 0), 0), 0), 0), 0), 0);
 }
 
 impl Encode for EventKind {
@@ -1203,6 +2060,8 @@ impl Encode for EventKind {
             EventKind::Trace => 0,
             EventKind::TraceAggregate => 0,
             EventKind::TraceAggregateDelta => 0,
+            EventKind::ClockAnchor => 0,
+            EventKind::Heartbeat => 0,
         }
     }
 
@@ -1228,6 +2087,14 @@ impl Encode for EventKind {
                 cursor.base(1)[0] = 4;
                 cursor.base(Self::BASE_LEN - 1).fill(0);
             }
+            EventKind::ClockAnchor => {
+                cursor.base(1)[0] = 5;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            EventKind::Heartbeat => {
+                cursor.base(1)[0] = 6;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
         }
     }
 }
@@ -1256,13 +2123,21 @@ impl<'a> Decode<'a> for EventKind {
                 cursor.advance(Self::BASE_LEN - 1);
                 Ok(EventKind::TraceAggregateDelta)
             }
+            5 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(EventKind::ClockAnchor)
+            }
+            6 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(EventKind::Heartbeat)
+            }
             _ => { Err(DecodeError) }
         }
     }
 }
 
 impl BaseLen for EventKindLazy {
-    const BASE_LEN: usize = 1 + max(max(max(max(max(0, 0), 0), 0), 0), 0);
+    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(0, 0), 0), 0), 0), 0), 0), 0);
 }
 
 impl Encode for EventKindLazy {
@@ -1273,6 +2148,8 @@ impl Encode for EventKindLazy {
             EventKindLazy::Trace => 0,
             EventKindLazy::TraceAggregate => 0,
             EventKindLazy::TraceAggregateDelta => 0,
+            EventKindLazy::ClockAnchor => 0,
+            EventKindLazy::Heartbeat => 0,
         }
     }
 
@@ -1298,6 +2175,14 @@ impl Encode for EventKindLazy {
                 cursor.base(1)[0] = 4;
                 cursor.base(Self::BASE_LEN - 1).fill(0);
             }
+            EventKindLazy::ClockAnchor => {
+                cursor.base(1)[0] = 5;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            EventKindLazy::Heartbeat => {
+                cursor.base(1)[0] = 6;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
         }
     }
 }
@@ -1326,6 +2211,14 @@ impl<'a> Decode<'a> for EventKindLazy {
                 cursor.advance(Self::BASE_LEN - 1);
                 Ok(EventKindLazy::TraceAggregateDelta)
             }
+            5 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(EventKindLazy::ClockAnchor)
+            }
+            6 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(EventKindLazy::Heartbeat)
+            }
             _ => { Err(DecodeError) }
         }
     }
@@ -1341,6 +2234,8 @@ impl TryFrom<EventKindLazy> for EventKind {
             EventKindLazy::Trace => Ok(EventKind::Trace),
             EventKindLazy::TraceAggregate => Ok(EventKind::TraceAggregate),
             EventKindLazy::TraceAggregateDelta => Ok(EventKind::TraceAggregateDelta),
+            EventKindLazy::ClockAnchor => Ok(EventKind::ClockAnchor),
+            EventKindLazy::Heartbeat => Ok(EventKind::Heartbeat),
         }
     }
 }
@@ -1676,17 +2571,224 @@ impl<'a> Encode for TraceLazy<'a> {
 
     fn encode(&self, cursor: &mut EncodeCursor) {
         let start_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let trace: mproto::ListLazy<'a, u8> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        let trace: mproto::ListLazy<'a, u8> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        start_nanos.encode(cursor);
+        trace.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for TraceLazy<'a> {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let offset = cursor.offset();
+        cursor.advance(Self::BASE_LEN);
+        Ok(TraceLazy {
+            buffer: cursor.buffer(),
+            offset,
+        })
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> TryFrom<TraceLazy<'a>> for Trace {
+    type Error = DecodeError;
+
+    fn try_from(other: TraceLazy<'a>) -> Result<Self, Self::Error> {
+        let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
+        Decode::decode(&cursor)
+    }
+}
+
+impl<'a> Copy for TraceLazy<'a> { }
+
+impl<'a> Clone for TraceLazy<'a> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer,
+            offset: self.offset,
+        }
+    }
+}
+
+impl<'a> core::fmt::Debug for TraceLazy<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TraceLazy")
+            .finish()
+    }
+}
+
+impl<'a> PartialEq for TraceLazy<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.start_nanos().unwrap() == other.start_nanos().unwrap()
+            && self.trace().unwrap() == other.trace().unwrap()
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct TraceAggregate {
+    pub start_nanos: u64,
+    pub nodes: Vec<TraceAggregateNode>,
+    pub counters: Vec<u32>,
+    pub metrics: Vec<MetricAggregate>,
+}
+
+pub struct TraceAggregateLazy<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+}
+
+pub struct TraceAggregateGen<
+    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
+    Counters: Encode + Compatible<Vec<u32>>,
+    Metrics: Encode + Compatible<Vec<MetricAggregate>>,
+> {
+    pub start_nanos: u64,
+    pub nodes: Nodes,
+    pub counters: Counters,
+    pub metrics: Metrics,
+}
+
+impl<
+    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
+    Counters: Encode + Compatible<Vec<u32>>,
+    Metrics: Encode + Compatible<Vec<MetricAggregate>>
+> Compatible<TraceAggregate> for TraceAggregateGen<Nodes, Counters, Metrics> { }
+impl<
+    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
+    Counters: Encode + Compatible<Vec<u32>>,
+    Metrics: Encode + Compatible<Vec<MetricAggregate>>
+> Compatible<TraceAggregateGen<Nodes, Counters, Metrics>> for TraceAggregate { }
+
+impl<
+    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
+    Counters: Encode + Compatible<Vec<u32>>,
+    Metrics: Encode + Compatible<Vec<MetricAggregate>>,
+> BaseLen for TraceAggregateGen<Nodes, Counters, Metrics> {
+    const BASE_LEN: usize = 8 + Nodes::BASE_LEN + Counters::BASE_LEN + Metrics::BASE_LEN;
+}
+
+impl<
+    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
+    Counters: Encode + Compatible<Vec<u32>>,
+    Metrics: Encode + Compatible<Vec<MetricAggregate>>,
+> Encode for TraceAggregateGen<Nodes, Counters, Metrics> {
+    fn scratch_len(&self) -> usize {
+        self.start_nanos.scratch_len() + self.nodes.scratch_len() + self.counters.scratch_len() + self.metrics.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.start_nanos.encode(cursor);
+        self.nodes.encode(cursor);
+        self.counters.encode(cursor);
+        self.metrics.encode(cursor);
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Owned for TraceAggregate {
+    type Lazy<'a> = TraceAggregateLazy<'a>;
+
+    fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
+        TryFrom::try_from(lazy)
+    }
+}
+
+impl<'a> Lazy<'a> for TraceAggregateLazy<'a> {
+    type Owned = TraceAggregate;
+}
+
+impl<'a> Compatible<TraceAggregateLazy<'a>> for TraceAggregateLazy<'a> { }
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> Compatible<TraceAggregateLazy<'a>> for TraceAggregate { }
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Compatible<TraceAggregate> for TraceAggregate { }
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> Compatible<TraceAggregate> for TraceAggregateLazy<'a> { }
+
+impl<'a> TraceAggregateLazy<'a> {
+
+    pub fn start_nanos(&self) -> DecodeResult<u64> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
+    }
+
+    pub fn nodes(&self) -> DecodeResult<mproto::ListLazy<'a, TraceAggregateNode>> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8))
+    }
+
+    pub fn counters(&self) -> DecodeResult<mproto::ListLazy<'a, u32>> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16))
+    }
+
+    pub fn metrics(&self) -> DecodeResult<mproto::ListLazy<'a, MetricAggregate>> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl BaseLen for TraceAggregate {
+    const BASE_LEN: usize = 32;
+}
+
+impl Encode for TraceAggregate {
+    fn scratch_len(&self) -> usize {
+        self.start_nanos.scratch_len() + self.nodes.scratch_len() + self.counters.scratch_len() + self.metrics.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.start_nanos.encode(cursor);
+        self.nodes.encode(cursor);
+        self.counters.encode(cursor);
+        self.metrics.encode(cursor);
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> Decode<'a> for TraceAggregate {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let start_nanos = Decode::decode(cursor)?;
+        let nodes = Decode::decode(cursor)?;
+        let counters = Decode::decode(cursor)?;
+        let metrics = Decode::decode(cursor)?;
+
+        Ok(TraceAggregate {
+            start_nanos,
+            nodes,
+            counters,
+            metrics,
+        })
+    }
+}
+
+impl<'a> BaseLen for TraceAggregateLazy<'a> {
+    const BASE_LEN: usize = 32;
+}
+
+impl<'a> Encode for TraceAggregateLazy<'a> {
+    fn scratch_len(&self) -> usize {
+        let start_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let nodes: mproto::ListLazy<'a, TraceAggregateNode> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        let counters: mproto::ListLazy<'a, u32> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
+        let metrics: mproto::ListLazy<'a, MetricAggregate> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
+        start_nanos.scratch_len() + nodes.scratch_len() + counters.scratch_len() + metrics.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        let start_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let nodes: mproto::ListLazy<'a, TraceAggregateNode> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        let counters: mproto::ListLazy<'a, u32> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
+        let metrics: mproto::ListLazy<'a, MetricAggregate> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
         start_nanos.encode(cursor);
-        trace.encode(cursor);
+        nodes.encode(cursor);
+        counters.encode(cursor);
+        metrics.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for TraceLazy<'a> {
+impl<'a> Decode<'a> for TraceAggregateLazy<'a> {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
         let offset = cursor.offset();
         cursor.advance(Self::BASE_LEN);
-        Ok(TraceLazy {
+        Ok(TraceAggregateLazy {
             buffer: cursor.buffer(),
             offset,
         })
@@ -1694,18 +2796,18 @@ impl<'a> Decode<'a> for TraceLazy<'a> {
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
-impl<'a> TryFrom<TraceLazy<'a>> for Trace {
+impl<'a> TryFrom<TraceAggregateLazy<'a>> for TraceAggregate {
     type Error = DecodeError;
 
-    fn try_from(other: TraceLazy<'a>) -> Result<Self, Self::Error> {
+    fn try_from(other: TraceAggregateLazy<'a>) -> Result<Self, Self::Error> {
         let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
         Decode::decode(&cursor)
     }
 }
 
-impl<'a> Copy for TraceLazy<'a> { }
+impl<'a> Copy for TraceAggregateLazy<'a> { }
 
-impl<'a> Clone for TraceLazy<'a> {
+impl<'a> Clone for TraceAggregateLazy<'a> {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer,
@@ -1714,109 +2816,104 @@ impl<'a> Clone for TraceLazy<'a> {
     }
 }
 
-impl<'a> core::fmt::Debug for TraceLazy<'a> {
+impl<'a> core::fmt::Debug for TraceAggregateLazy<'a> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("TraceLazy")
+        f.debug_struct("TraceAggregateLazy")
             .finish()
     }
 }
 
-impl<'a> PartialEq for TraceLazy<'a> {
+impl<'a> PartialEq for TraceAggregateLazy<'a> {
     fn eq(&self, other: &Self) -> bool {
         self.start_nanos().unwrap() == other.start_nanos().unwrap()
-            && self.trace().unwrap() == other.trace().unwrap()
+            && self.nodes().unwrap() == other.nodes().unwrap()&& self.counters().unwrap() == other.counters().unwrap()&& self.metrics().unwrap() == other.metrics().unwrap()
     }
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub struct TraceAggregate {
+pub struct TraceAggregateDelta {
     pub start_nanos: u64,
-    pub nodes: Vec<TraceAggregateNode>,
+    pub end_nanos: u64,
     pub counters: Vec<u32>,
     pub metrics: Vec<MetricAggregate>,
 }
 
-pub struct TraceAggregateLazy<'a> {
+pub struct TraceAggregateDeltaLazy<'a> {
     buffer: &'a [u8],
     offset: usize,
 }
 
-pub struct TraceAggregateGen<
-    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
+pub struct TraceAggregateDeltaGen<
     Counters: Encode + Compatible<Vec<u32>>,
     Metrics: Encode + Compatible<Vec<MetricAggregate>>,
 > {
     pub start_nanos: u64,
-    pub nodes: Nodes,
+    pub end_nanos: u64,
     pub counters: Counters,
     pub metrics: Metrics,
 }
 
 impl<
-    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
     Counters: Encode + Compatible<Vec<u32>>,
     Metrics: Encode + Compatible<Vec<MetricAggregate>>
-> Compatible<TraceAggregate> for TraceAggregateGen<Nodes, Counters, Metrics> { }
+> Compatible<TraceAggregateDelta> for TraceAggregateDeltaGen<Counters, Metrics> { }
 impl<
-    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
     Counters: Encode + Compatible<Vec<u32>>,
     Metrics: Encode + Compatible<Vec<MetricAggregate>>
-> Compatible<TraceAggregateGen<Nodes, Counters, Metrics>> for TraceAggregate { }
+> Compatible<TraceAggregateDeltaGen<Counters, Metrics>> for TraceAggregateDelta { }
 
 impl<
-    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
     Counters: Encode + Compatible<Vec<u32>>,
     Metrics: Encode + Compatible<Vec<MetricAggregate>>,
-> BaseLen for TraceAggregateGen<Nodes, Counters, Metrics> {
-    const BASE_LEN: usize = 8 + Nodes::BASE_LEN + Counters::BASE_LEN + Metrics::BASE_LEN;
+> BaseLen for TraceAggregateDeltaGen<Counters, Metrics> {
+    const BASE_LEN: usize = 16 + Counters::BASE_LEN + Metrics::BASE_LEN;
 }
 
 impl<
-    Nodes: Encode + Compatible<Vec<TraceAggregateNode>>,
     Counters: Encode + Compatible<Vec<u32>>,
     Metrics: Encode + Compatible<Vec<MetricAggregate>>,
-> Encode for TraceAggregateGen<Nodes, Counters, Metrics> {
+> Encode for TraceAggregateDeltaGen<Counters, Metrics> {
     fn scratch_len(&self) -> usize {
-        self.start_nanos.scratch_len() + self.nodes.scratch_len() + self.counters.scratch_len() + self.metrics.scratch_len()
+        self.start_nanos.scratch_len() + self.end_nanos.scratch_len() + self.counters.scratch_len() + self.metrics.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
         self.start_nanos.encode(cursor);
-        self.nodes.encode(cursor);
+        self.end_nanos.encode(cursor);
         self.counters.encode(cursor);
         self.metrics.encode(cursor);
     }
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
-impl Owned for TraceAggregate {
-    type Lazy<'a> = TraceAggregateLazy<'a>;
+impl Owned for TraceAggregateDelta {
+    type Lazy<'a> = TraceAggregateDeltaLazy<'a>;
 
     fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
         TryFrom::try_from(lazy)
     }
 }
 
-impl<'a> Lazy<'a> for TraceAggregateLazy<'a> {
-    type Owned = TraceAggregate;
+impl<'a> Lazy<'a> for TraceAggregateDeltaLazy<'a> {
+    type Owned = TraceAggregateDelta;
 }
 
-impl<'a> Compatible<TraceAggregateLazy<'a>> for TraceAggregateLazy<'a> { }
+impl<'a> Compatible<TraceAggregateDeltaLazy<'a>> for TraceAggregateDeltaLazy<'a> { }
 #[cfg(any(feature = "std", feature = "alloc"))]
-impl<'a> Compatible<TraceAggregateLazy<'a>> for TraceAggregate { }
+impl<'a> Compatible<TraceAggregateDeltaLazy<'a>> for TraceAggregateDelta { }
 #[cfg(any(feature = "std", feature = "alloc"))]
-impl Compatible<TraceAggregate> for TraceAggregate { }
+impl Compatible<TraceAggregateDelta> for TraceAggregateDelta { }
 #[cfg(any(feature = "std", feature = "alloc"))]
-impl<'a> Compatible<TraceAggregate> for TraceAggregateLazy<'a> { }
+impl<'a> Compatible<TraceAggregateDelta> for TraceAggregateDeltaLazy<'a> { }
 
-impl<'a> TraceAggregateLazy<'a> {
+impl<'a> TraceAggregateDeltaLazy<'a> {
 
     pub fn start_nanos(&self) -> DecodeResult<u64> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
     }
 
-    pub fn nodes(&self) -> DecodeResult<mproto::ListLazy<'a, TraceAggregateNode>> {
+    pub fn end_nanos(&self) -> DecodeResult<u64> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8))
     }
 
@@ -1830,70 +2927,70 @@ impl<'a> TraceAggregateLazy<'a> {
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
-impl BaseLen for TraceAggregate {
+impl BaseLen for TraceAggregateDelta {
     const BASE_LEN: usize = 32;
 }
 
-impl Encode for TraceAggregate {
+impl Encode for TraceAggregateDelta {
     fn scratch_len(&self) -> usize {
-        self.start_nanos.scratch_len() + self.nodes.scratch_len() + self.counters.scratch_len() + self.metrics.scratch_len()
+        self.start_nanos.scratch_len() + self.end_nanos.scratch_len() + self.counters.scratch_len() + self.metrics.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
         self.start_nanos.encode(cursor);
-        self.nodes.encode(cursor);
+        self.end_nanos.encode(cursor);
         self.counters.encode(cursor);
         self.metrics.encode(cursor);
     }
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
-impl<'a> Decode<'a> for TraceAggregate {
+impl<'a> Decode<'a> for TraceAggregateDelta {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
         let start_nanos = Decode::decode(cursor)?;
-        let nodes = Decode::decode(cursor)?;
+        let end_nanos = Decode::decode(cursor)?;
         let counters = Decode::decode(cursor)?;
         let metrics = Decode::decode(cursor)?;
 
-        Ok(TraceAggregate {
+        Ok(TraceAggregateDelta {
             start_nanos,
-            nodes,
+            end_nanos,
             counters,
             metrics,
         })
     }
 }
 
-impl<'a> BaseLen for TraceAggregateLazy<'a> {
+impl<'a> BaseLen for TraceAggregateDeltaLazy<'a> {
     const BASE_LEN: usize = 32;
 }
 
-impl<'a> Encode for TraceAggregateLazy<'a> {
+impl<'a> Encode for TraceAggregateDeltaLazy<'a> {
     fn scratch_len(&self) -> usize {
         let start_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let nodes: mproto::ListLazy<'a, TraceAggregateNode> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        let end_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
         let counters: mproto::ListLazy<'a, u32> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
         let metrics: mproto::ListLazy<'a, MetricAggregate> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
-        start_nanos.scratch_len() + nodes.scratch_len() + counters.scratch_len() + metrics.scratch_len()
+        start_nanos.scratch_len() + end_nanos.scratch_len() + counters.scratch_len() + metrics.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
         let start_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let nodes: mproto::ListLazy<'a, TraceAggregateNode> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        let end_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
         let counters: mproto::ListLazy<'a, u32> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
         let metrics: mproto::ListLazy<'a, MetricAggregate> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
         start_nanos.encode(cursor);
-        nodes.encode(cursor);
+        end_nanos.encode(cursor);
         counters.encode(cursor);
         metrics.encode(cursor);
     }
 }
 
-impl<'a> Decode<'a> for TraceAggregateLazy<'a> {
+impl<'a> Decode<'a> for TraceAggregateDeltaLazy<'a> {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
         let offset = cursor.offset();
         cursor.advance(Self::BASE_LEN);
-        Ok(TraceAggregateLazy {
+        Ok(TraceAggregateDeltaLazy {
             buffer: cursor.buffer(),
             offset,
         })
@@ -1901,18 +2998,18 @@ impl<'a> Decode<'a> for TraceAggregateLazy<'a> {
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
-impl<'a> TryFrom<TraceAggregateLazy<'a>> for TraceAggregate {
+impl<'a> TryFrom<TraceAggregateDeltaLazy<'a>> for TraceAggregateDelta {
     type Error = DecodeError;
 
-    fn try_from(other: TraceAggregateLazy<'a>) -> Result<Self, Self::Error> {
+    fn try_from(other: TraceAggregateDeltaLazy<'a>) -> Result<Self, Self::Error> {
         let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
         Decode::decode(&cursor)
     }
 }
 
-impl<'a> Copy for TraceAggregateLazy<'a> { }
+impl<'a> Copy for TraceAggregateDeltaLazy<'a> { }
 
-impl<'a> Clone for TraceAggregateLazy<'a> {
+impl<'a> Clone for TraceAggregateDeltaLazy<'a> {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer,
@@ -1921,200 +3018,369 @@ impl<'a> Clone for TraceAggregateLazy<'a> {
     }
 }
 
-impl<'a> core::fmt::Debug for TraceAggregateLazy<'a> {
+impl<'a> core::fmt::Debug for TraceAggregateDeltaLazy<'a> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("TraceAggregateLazy")
+        f.debug_struct("TraceAggregateDeltaLazy")
             .finish()
     }
 }
 
-impl<'a> PartialEq for TraceAggregateLazy<'a> {
+impl<'a> PartialEq for TraceAggregateDeltaLazy<'a> {
     fn eq(&self, other: &Self) -> bool {
         self.start_nanos().unwrap() == other.start_nanos().unwrap()
-            && self.nodes().unwrap() == other.nodes().unwrap()&& self.counters().unwrap() == other.counters().unwrap()&& self.metrics().unwrap() == other.metrics().unwrap()
+            && self.end_nanos().unwrap() == other.end_nanos().unwrap()&& self.counters().unwrap() == other.counters().unwrap()&& self.metrics().unwrap() == other.metrics().unwrap()
     }
 }
 
-#[cfg(any(feature = "std", feature = "alloc"))]
-#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub struct TraceAggregateDelta {
-    pub start_nanos: u64,
-    pub end_nanos: u64,
-    pub counters: Vec<u32>,
-    pub metrics: Vec<MetricAggregate>,
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct ClockAnchor {
+    pub monotonic_nanos: u64,
+    pub unix_nanos: u64,
 }
 
-pub struct TraceAggregateDeltaLazy<'a> {
+pub struct ClockAnchorLazy<'a> {
     buffer: &'a [u8],
     offset: usize,
 }
 
-pub struct TraceAggregateDeltaGen<
-    Counters: Encode + Compatible<Vec<u32>>,
-    Metrics: Encode + Compatible<Vec<MetricAggregate>>,
-> {
-    pub start_nanos: u64,
-    pub end_nanos: u64,
-    pub counters: Counters,
-    pub metrics: Metrics,
+pub struct ClockAnchorGen<> {
+    pub monotonic_nanos: u64,
+    pub unix_nanos: u64,
 }
 
-impl<
-    Counters: Encode + Compatible<Vec<u32>>,
-    Metrics: Encode + Compatible<Vec<MetricAggregate>>
-> Compatible<TraceAggregateDelta> for TraceAggregateDeltaGen<Counters, Metrics> { }
-impl<
-    Counters: Encode + Compatible<Vec<u32>>,
-    Metrics: Encode + Compatible<Vec<MetricAggregate>>
-> Compatible<TraceAggregateDeltaGen<Counters, Metrics>> for TraceAggregateDelta { }
+impl<> Compatible<ClockAnchor> for ClockAnchorGen<> { }
+impl<> Compatible<ClockAnchorGen<>> for ClockAnchor { }
 
-impl<
-    Counters: Encode + Compatible<Vec<u32>>,
-    Metrics: Encode + Compatible<Vec<MetricAggregate>>,
-> BaseLen for TraceAggregateDeltaGen<Counters, Metrics> {
-    const BASE_LEN: usize = 16 + Counters::BASE_LEN + Metrics::BASE_LEN;
+impl<> BaseLen for ClockAnchorGen<> {
+    const BASE_LEN: usize = 16;
 }
 
-impl<
-    Counters: Encode + Compatible<Vec<u32>>,
-    Metrics: Encode + Compatible<Vec<MetricAggregate>>,
-> Encode for TraceAggregateDeltaGen<Counters, Metrics> {
+impl<> Encode for ClockAnchorGen<> {
+    fn scratch_len(&self) -> usize {
+        self.monotonic_nanos.scratch_len() + self.unix_nanos.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.monotonic_nanos.encode(cursor);
+        self.unix_nanos.encode(cursor);
+    }
+}
+
+impl Owned for ClockAnchor {
+    type Lazy<'a> = ClockAnchorLazy<'a>;
+
+    fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
+        TryFrom::try_from(lazy)
+    }
+}
+
+impl<'a> Lazy<'a> for ClockAnchorLazy<'a> {
+    type Owned = ClockAnchor;
+}
+
+impl<'a> Compatible<ClockAnchorLazy<'a>> for ClockAnchorLazy<'a> { }
+impl<'a> Compatible<ClockAnchorLazy<'a>> for ClockAnchor { }
+impl Compatible<ClockAnchor> for ClockAnchor { }
+impl<'a> Compatible<ClockAnchor> for ClockAnchorLazy<'a> { }
+
+impl<'a> ClockAnchorLazy<'a> {
+
+    pub fn monotonic_nanos(&self) -> DecodeResult<u64> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
+    }
+
+    pub fn unix_nanos(&self) -> DecodeResult<u64> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8))
+    }
+}
+
+impl BaseLen for ClockAnchor {
+    const BASE_LEN: usize = 16;
+}
+
+impl Encode for ClockAnchor {
+    fn scratch_len(&self) -> usize {
+        self.monotonic_nanos.scratch_len() + self.unix_nanos.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        self.monotonic_nanos.encode(cursor);
+        self.unix_nanos.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for ClockAnchor {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let monotonic_nanos = Decode::decode(cursor)?;
+        let unix_nanos = Decode::decode(cursor)?;
+
+        Ok(ClockAnchor {
+            monotonic_nanos,
+            unix_nanos,
+        })
+    }
+}
+
+impl<'a> BaseLen for ClockAnchorLazy<'a> {
+    const BASE_LEN: usize = 16;
+}
+
+impl<'a> Encode for ClockAnchorLazy<'a> {
+    fn scratch_len(&self) -> usize {
+        let monotonic_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let unix_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        monotonic_nanos.scratch_len() + unix_nanos.scratch_len()
+    }
+
+    fn encode(&self, cursor: &mut EncodeCursor) {
+        let monotonic_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let unix_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        monotonic_nanos.encode(cursor);
+        unix_nanos.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for ClockAnchorLazy<'a> {
+    fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
+        let offset = cursor.offset();
+        cursor.advance(Self::BASE_LEN);
+        Ok(ClockAnchorLazy {
+            buffer: cursor.buffer(),
+            offset,
+        })
+    }
+}
+
+impl<'a> TryFrom<ClockAnchorLazy<'a>> for ClockAnchor {
+    type Error = DecodeError;
+
+    fn try_from(other: ClockAnchorLazy<'a>) -> Result<Self, Self::Error> {
+        let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
+        Decode::decode(&cursor)
+    }
+}
+
+impl<'a> Copy for ClockAnchorLazy<'a> { }
+
+impl<'a> Clone for ClockAnchorLazy<'a> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer,
+            offset: self.offset,
+        }
+    }
+}
+
+impl<'a> core::fmt::Debug for ClockAnchorLazy<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ClockAnchorLazy")
+            .finish()
+    }
+}
+
+impl<'a> PartialEq for ClockAnchorLazy<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.monotonic_nanos().unwrap() == other.monotonic_nanos().unwrap()
+            && self.unix_nanos().unwrap() == other.unix_nanos().unwrap()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct Heartbeat {
+    pub uptime_nanos: u64,
+    pub live_sources: u64,
+    pub buffer_count: u32,
+    pub retry_buffers: u32,
+    pub sent_buffers: u64,
+    pub dropped_events: u64,
+    pub dropped_buffers: u64,
+}
+
+pub struct HeartbeatLazy<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+}
+
+pub struct HeartbeatGen<> {
+    pub uptime_nanos: u64,
+    pub live_sources: u64,
+    pub buffer_count: u32,
+    pub retry_buffers: u32,
+    pub sent_buffers: u64,
+    pub dropped_events: u64,
+    pub dropped_buffers: u64,
+}
+
+impl<> Compatible<Heartbeat> for HeartbeatGen<> { }
+impl<> Compatible<HeartbeatGen<>> for Heartbeat { }
+
+impl<> BaseLen for HeartbeatGen<> {
+    const BASE_LEN: usize = 48;
+}
+
+impl<> Encode for HeartbeatGen<> {
     fn scratch_len(&self) -> usize {
-        self.start_nanos.scratch_len() + self.end_nanos.scratch_len() + self.counters.scratch_len() + self.metrics.scratch_len()
+        self.uptime_nanos.scratch_len() + self.live_sources.scratch_len() + self.buffer_count.scratch_len() + self.retry_buffers.scratch_len() + self.sent_buffers.scratch_len() + self.dropped_events.scratch_len() + self.dropped_buffers.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.start_nanos.encode(cursor);
-        self.end_nanos.encode(cursor);
-        self.counters.encode(cursor);
-        self.metrics.encode(cursor);
+        self.uptime_nanos.encode(cursor);
+        self.live_sources.encode(cursor);
+        self.buffer_count.encode(cursor);
+        self.retry_buffers.encode(cursor);
+        self.sent_buffers.encode(cursor);
+        self.dropped_events.encode(cursor);
+        self.dropped_buffers.encode(cursor);
     }
 }
 
-#[cfg(any(feature = "std", feature = "alloc"))]
-impl Owned for TraceAggregateDelta {
-    type Lazy<'a> = TraceAggregateDeltaLazy<'a>;
+impl Owned for Heartbeat {
+    type Lazy<'a> = HeartbeatLazy<'a>;
 
     fn lazy_to_owned(lazy: Self::Lazy<'_>) -> DecodeResult<Self> {
         TryFrom::try_from(lazy)
     }
 }
 
-impl<'a> Lazy<'a> for TraceAggregateDeltaLazy<'a> {
-    type Owned = TraceAggregateDelta;
+impl<'a> Lazy<'a> for HeartbeatLazy<'a> {
+    type Owned = Heartbeat;
 }
 
-impl<'a> Compatible<TraceAggregateDeltaLazy<'a>> for TraceAggregateDeltaLazy<'a> { }
-#[cfg(any(feature = "std", feature = "alloc"))]
-impl<'a> Compatible<TraceAggregateDeltaLazy<'a>> for TraceAggregateDelta { }
-#[cfg(any(feature = "std", feature = "alloc"))]
-impl Compatible<TraceAggregateDelta> for TraceAggregateDelta { }
-#[cfg(any(feature = "std", feature = "alloc"))]
-impl<'a> Compatible<TraceAggregateDelta> for TraceAggregateDeltaLazy<'a> { }
+impl<'a> Compatible<HeartbeatLazy<'a>> for HeartbeatLazy<'a> { }
+impl<'a> Compatible<HeartbeatLazy<'a>> for Heartbeat { }
+impl Compatible<Heartbeat> for Heartbeat { }
+impl<'a> Compatible<Heartbeat> for HeartbeatLazy<'a> { }
 
-impl<'a> TraceAggregateDeltaLazy<'a> {
+impl<'a> HeartbeatLazy<'a> {
 
-    pub fn start_nanos(&self) -> DecodeResult<u64> {
+    pub fn uptime_nanos(&self) -> DecodeResult<u64> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0))
     }
 
-    pub fn end_nanos(&self) -> DecodeResult<u64> {
+    pub fn live_sources(&self) -> DecodeResult<u64> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8))
     }
 
-    pub fn counters(&self) -> DecodeResult<mproto::ListLazy<'a, u32>> {
+    pub fn buffer_count(&self) -> DecodeResult<u32> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16))
     }
 
-    pub fn metrics(&self) -> DecodeResult<mproto::ListLazy<'a, MetricAggregate>> {
+    pub fn retry_buffers(&self) -> DecodeResult<u32> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 20))
+    }
+
+    pub fn sent_buffers(&self) -> DecodeResult<u64> {
         Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24))
     }
+
+    pub fn dropped_events(&self) -> DecodeResult<u64> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 32))
+    }
+
+    pub fn dropped_buffers(&self) -> DecodeResult<u64> {
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 40))
+    }
 }
 
-#[cfg(any(feature = "std", feature = "alloc"))]
-impl BaseLen for TraceAggregateDelta {
-    const BASE_LEN: usize = 32;
+impl BaseLen for Heartbeat {
+    const BASE_LEN: usize = 48;
 }
 
-impl Encode for TraceAggregateDelta {
+impl Encode for Heartbeat {
     fn scratch_len(&self) -> usize {
-        self.start_nanos.scratch_len() + self.end_nanos.scratch_len() + self.counters.scratch_len() + self.metrics.scratch_len()
+        self.uptime_nanos.scratch_len() + self.live_sources.scratch_len() + self.buffer_count.scratch_len() + self.retry_buffers.scratch_len() + self.sent_buffers.scratch_len() + self.dropped_events.scratch_len() + self.dropped_buffers.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        self.start_nanos.encode(cursor);
-        self.end_nanos.encode(cursor);
-        self.counters.encode(cursor);
-        self.metrics.encode(cursor);
+        self.uptime_nanos.encode(cursor);
+        self.live_sources.encode(cursor);
+        self.buffer_count.encode(cursor);
+        self.retry_buffers.encode(cursor);
+        self.sent_buffers.encode(cursor);
+        self.dropped_events.encode(cursor);
+        self.dropped_buffers.encode(cursor);
     }
 }
 
-#[cfg(any(feature = "std", feature = "alloc"))]
-impl<'a> Decode<'a> for TraceAggregateDelta {
+impl<'a> Decode<'a> for Heartbeat {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
-        let start_nanos = Decode::decode(cursor)?;
-        let end_nanos = Decode::decode(cursor)?;
-        let counters = Decode::decode(cursor)?;
-        let metrics = Decode::decode(cursor)?;
-
-        Ok(TraceAggregateDelta {
-            start_nanos,
-            end_nanos,
-            counters,
-            metrics,
+        let uptime_nanos = Decode::decode(cursor)?;
+        let live_sources = Decode::decode(cursor)?;
+        let buffer_count = Decode::decode(cursor)?;
+        let retry_buffers = Decode::decode(cursor)?;
+        let sent_buffers = Decode::decode(cursor)?;
+        let dropped_events = Decode::decode(cursor)?;
+        let dropped_buffers = Decode::decode(cursor)?;
+
+        Ok(Heartbeat {
+            uptime_nanos,
+            live_sources,
+            buffer_count,
+            retry_buffers,
+            sent_buffers,
+            dropped_events,
+            dropped_buffers,
         })
     }
 }
 
-impl<'a> BaseLen for TraceAggregateDeltaLazy<'a> {
-    const BASE_LEN: usize = 32;
+impl<'a> BaseLen for HeartbeatLazy<'a> {
+    const BASE_LEN: usize = 48;
 }
 
-impl<'a> Encode for TraceAggregateDeltaLazy<'a> {
+impl<'a> Encode for HeartbeatLazy<'a> {
     fn scratch_len(&self) -> usize {
-        let start_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let end_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
-        let counters: mproto::ListLazy<'a, u32> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
-        let metrics: mproto::ListLazy<'a, MetricAggregate> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
-        start_nanos.scratch_len() + end_nanos.scratch_len() + counters.scratch_len() + metrics.scratch_len()
+        let uptime_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let live_sources: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        let buffer_count: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
+        let retry_buffers: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 20)).unwrap();
+        let sent_buffers: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
+        let dropped_events: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 32)).unwrap();
+        let dropped_buffers: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 40)).unwrap();
+        uptime_nanos.scratch_len() + live_sources.scratch_len() + buffer_count.scratch_len() + retry_buffers.scratch_len() + sent_buffers.scratch_len() + dropped_events.scratch_len() + dropped_buffers.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
-        let start_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let end_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
-        let counters: mproto::ListLazy<'a, u32> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
-        let metrics: mproto::ListLazy<'a, MetricAggregate> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
-        start_nanos.encode(cursor);
-        end_nanos.encode(cursor);
-        counters.encode(cursor);
-        metrics.encode(cursor);
-    }
-}
-
-impl<'a> Decode<'a> for TraceAggregateDeltaLazy<'a> {
+        let uptime_nanos: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
+        let live_sources: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 8)).unwrap();
+        let buffer_count: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 16)).unwrap();
+        let retry_buffers: u32 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 20)).unwrap();
+        let sent_buffers: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 24)).unwrap();
+        let dropped_events: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 32)).unwrap();
+        let dropped_buffers: u64 = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 40)).unwrap();
+        uptime_nanos.encode(cursor);
+        live_sources.encode(cursor);
+        buffer_count.encode(cursor);
+        retry_buffers.encode(cursor);
+        sent_buffers.encode(cursor);
+        dropped_events.encode(cursor);
+        dropped_buffers.encode(cursor);
+    }
+}
+
+impl<'a> Decode<'a> for HeartbeatLazy<'a> {
     fn decode(cursor: &DecodeCursor<'a>) -> DecodeResult<Self> {
         let offset = cursor.offset();
         cursor.advance(Self::BASE_LEN);
-        Ok(TraceAggregateDeltaLazy {
+        Ok(HeartbeatLazy {
             buffer: cursor.buffer(),
             offset,
         })
     }
 }
 
-#[cfg(any(feature = "std", feature = "alloc"))]
-impl<'a> TryFrom<TraceAggregateDeltaLazy<'a>> for TraceAggregateDelta {
+impl<'a> TryFrom<HeartbeatLazy<'a>> for Heartbeat {
     type Error = DecodeError;
 
-    fn try_from(other: TraceAggregateDeltaLazy<'a>) -> Result<Self, Self::Error> {
+    fn try_from(other: HeartbeatLazy<'a>) -> Result<Self, Self::Error> {
         let cursor = DecodeCursor::at_offset(other.buffer, other.offset);
         Decode::decode(&cursor)
     }
 }
 
-impl<'a> Copy for TraceAggregateDeltaLazy<'a> { }
+impl<'a> Copy for HeartbeatLazy<'a> { }
 
-impl<'a> Clone for TraceAggregateDeltaLazy<'a> {
+impl<'a> Clone for HeartbeatLazy<'a> {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer,
@@ -2123,17 +3389,17 @@ impl<'a> Clone for TraceAggregateDeltaLazy<'a> {
     }
 }
 
-impl<'a> core::fmt::Debug for TraceAggregateDeltaLazy<'a> {
+impl<'a> core::fmt::Debug for HeartbeatLazy<'a> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("TraceAggregateDeltaLazy")
+        f.debug_struct("HeartbeatLazy")
             .finish()
     }
 }
 
-impl<'a> PartialEq for TraceAggregateDeltaLazy<'a> {
+impl<'a> PartialEq for HeartbeatLazy<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.start_nanos().unwrap() == other.start_nanos().unwrap()
-            && self.end_nanos().unwrap() == other.end_nanos().unwrap()&& self.counters().unwrap() == other.counters().unwrap()&& self.metrics().unwrap() == other.metrics().unwrap()
+        self.uptime_nanos().unwrap() == other.uptime_nanos().unwrap()
+            && self.live_sources().unwrap() == other.live_sources().unwrap()&& self.buffer_count().unwrap() == other.buffer_count().unwrap()&& self.retry_buffers().unwrap() == other.retry_buffers().unwrap()&& self.sent_buffers().unwrap() == other.sent_buffers().unwrap()&& self.dropped_events().unwrap() == other.dropped_events().unwrap()&& self.dropped_buffers().unwrap() == other.dropped_buffers().unwrap()
     }
 }
 
@@ -2223,17 +3489,17 @@ impl<'a> TraceAggregateNodeLazy<'a> {
     }
 
     pub fn branch_next(&self) -> DecodeResult<Option<u16>> {
-        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32)))
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6)))
     }
 
     pub fn next(&self) -> DecodeResult<Option<u16>> {
-        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 4 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32)))
+        Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 4 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6)))
     }
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
 impl BaseLen for TraceAggregateNode {
-    const BASE_LEN: usize = 7 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32);
+    const BASE_LEN: usize = 7 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6);
 }
 
 impl Encode for TraceAggregateNode {
@@ -2264,21 +3530,21 @@ impl<'a> Decode<'a> for TraceAggregateNode {
 }
 
 impl<'a> BaseLen for TraceAggregateNodeLazy<'a> {
-    const BASE_LEN: usize = 7 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32);
+    const BASE_LEN: usize = 7 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6);
 }
 
 impl<'a> Encode for TraceAggregateNodeLazy<'a> {
     fn scratch_len(&self) -> usize {
         let op: TraceOpAggregateLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let branch_next: Option<u16> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32))).unwrap();
-        let next: Option<u16> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 4 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32))).unwrap();
+        let branch_next: Option<u16> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6))).unwrap();
+        let next: Option<u16> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 4 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6))).unwrap();
         op.scratch_len() + branch_next.scratch_len() + next.scratch_len()
     }
 
     fn encode(&self, cursor: &mut EncodeCursor) {
         let op: TraceOpAggregateLazy<'a> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 0)).unwrap();
-        let branch_next: Option<u16> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32))).unwrap();
-        let next: Option<u16> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 4 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32))).unwrap();
+        let branch_next: Option<u16> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6))).unwrap();
+        let next: Option<u16> = Decode::decode(&DecodeCursor::at_offset(self.buffer, self.offset + 4 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6))).unwrap();
         op.encode(cursor);
         branch_next.encode(cursor);
         next.encode(cursor);
@@ -2374,6 +3640,11 @@ pub enum TraceOp {
         from: GlobalSourceId,
         to: GlobalSourceId,
     },
+    Panicked,
+    Success,
+    Failure,
+    Cancelled,
+    FutureStart,
 }
 
 #[derive(Clone)]
@@ -2419,6 +3690,11 @@ pub enum TraceOpLazy<'a> {
         from: GlobalSourceIdLazy<'a>,
         to: GlobalSourceIdLazy<'a>,
     },
+    Panicked,
+    Success,
+    Failure,
+    Cancelled,
+    FutureStart,
 }
 
 impl<'a> Compatible<TraceOpLazy<'a>> for TraceOpLazy<'a> { }
@@ -2439,7 +3715,7 @@ impl<'a> Lazy<'a> for TraceOpLazy<'a> {
 }
 
 impl BaseLen for TraceOp {
-    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 8), 8), 8), 0), 0), 0), 0), 0), 8), 8), 8), 28), 16), 16), 16), 32);
+    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 8), 8), 8), 0), 0), 0), 0), 0), 8), 8), 8), 28), 16), 16), 16), 32), 0), 0), 0), 0), 0);
 }
 
 impl Encode for TraceOp {
@@ -2483,6 +3759,11 @@ impl Encode for TraceOp {
             TraceOp::GlobalChannelTransfer { from, to } => {
                 from.scratch_len() + to.scratch_len()
             }
+            TraceOp::Panicked => 0,
+            TraceOp::Success => 0,
+            TraceOp::Failure => 0,
+            TraceOp::Cancelled => 0,
+            TraceOp::FutureStart => 0,
         }
     }
 
@@ -2566,6 +3847,26 @@ impl Encode for TraceOp {
                 to.encode(cursor);
                 cursor.base(Self::BASE_LEN - 1 - (32)).fill(0);
             }
+            TraceOp::Panicked => {
+                cursor.base(1)[0] = 16;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            TraceOp::Success => {
+                cursor.base(1)[0] = 17;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            TraceOp::Failure => {
+                cursor.base(1)[0] = 18;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            TraceOp::Cancelled => {
+                cursor.base(1)[0] = 19;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            TraceOp::FutureStart => {
+                cursor.base(1)[0] = 20;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
         }
     }
 }
@@ -2677,13 +3978,33 @@ impl<'a> Decode<'a> for TraceOp {
                     to,
                 })
             }
+            16 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOp::Panicked)
+            }
+            17 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOp::Success)
+            }
+            18 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOp::Failure)
+            }
+            19 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOp::Cancelled)
+            }
+            20 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOp::FutureStart)
+            }
             _ => { Err(DecodeError) }
         }
     }
 }
 
 impl<'a> BaseLen for TraceOpLazy<'a> {
-    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 8), 8), 8), 0), 0), 0), 0), 0), 8), 8), 8), 28), 16), 16), 16), 32);
+    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 8), 8), 8), 0), 0), 0), 0), 0), 8), 8), 8), 28), 16), 16), 16), 32), 0), 0), 0), 0), 0);
 }
 
 impl<'a> Encode for TraceOpLazy<'a> {
@@ -2727,6 +4048,11 @@ impl<'a> Encode for TraceOpLazy<'a> {
             TraceOpLazy::GlobalChannelTransfer { from, to } => {
                 from.scratch_len() + to.scratch_len()
             }
+            TraceOpLazy::Panicked => 0,
+            TraceOpLazy::Success => 0,
+            TraceOpLazy::Failure => 0,
+            TraceOpLazy::Cancelled => 0,
+            TraceOpLazy::FutureStart => 0,
         }
     }
 
@@ -2810,6 +4136,26 @@ impl<'a> Encode for TraceOpLazy<'a> {
                 to.encode(cursor);
                 cursor.base(Self::BASE_LEN - 1 - (32)).fill(0);
             }
+            TraceOpLazy::Panicked => {
+                cursor.base(1)[0] = 16;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            TraceOpLazy::Success => {
+                cursor.base(1)[0] = 17;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            TraceOpLazy::Failure => {
+                cursor.base(1)[0] = 18;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            TraceOpLazy::Cancelled => {
+                cursor.base(1)[0] = 19;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
+            TraceOpLazy::FutureStart => {
+                cursor.base(1)[0] = 20;
+                cursor.base(Self::BASE_LEN - 1).fill(0);
+            }
         }
     }
 }
@@ -2921,6 +4267,26 @@ impl<'a> Decode<'a> for TraceOpLazy<'a> {
                     to,
                 })
             }
+            16 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOpLazy::Panicked)
+            }
+            17 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOpLazy::Success)
+            }
+            18 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOpLazy::Failure)
+            }
+            19 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOpLazy::Cancelled)
+            }
+            20 => {
+                cursor.advance(Self::BASE_LEN - 1);
+                Ok(TraceOpLazy::FutureStart)
+            }
             _ => { Err(DecodeError) }
         }
     }
@@ -2994,6 +4360,11 @@ impl<'a> TryFrom<TraceOpLazy<'a>> for TraceOp {
                     to: Owned::lazy_to_owned(to)?,
                 })
             }
+            TraceOpLazy::Panicked => Ok(TraceOp::Panicked),
+            TraceOpLazy::Success => Ok(TraceOp::Success),
+            TraceOpLazy::Failure => Ok(TraceOp::Failure),
+            TraceOpLazy::Cancelled => Ok(TraceOp::Cancelled),
+            TraceOpLazy::FutureStart => Ok(TraceOp::FutureStart),
         }
     }
 }
@@ -3128,6 +4499,11 @@ impl<'a> PartialEq for TraceOpLazy<'a> {
                 self_from == other_from
                     && self_to == other_to
             }
+            (TraceOpLazy::Panicked, TraceOpLazy::Panicked) => true,
+            (TraceOpLazy::Success, TraceOpLazy::Success) => true,
+            (TraceOpLazy::Failure, TraceOpLazy::Failure) => true,
+            (TraceOpLazy::Cancelled, TraceOpLazy::Cancelled) => true,
+            (TraceOpLazy::FutureStart, TraceOpLazy::FutureStart) => true,
             #[allow(unreachable_patterns)]
             _ => false,
         }
@@ -3178,6 +4554,24 @@ pub enum TraceOpAggregate {
         from: GlobalSourceId,
         to: GlobalSourceId,
     },
+    Panicked {
+        index: u16,
+    },
+    Success {
+        index: u16,
+    },
+    Failure {
+        variant: Option<String>,
+        index: u16,
+    },
+    Cancelled {
+        index: u16,
+    },
+    FutureStart {
+        polls: u16,
+        busy_nanos: u16,
+        idle_nanos: u16,
+    },
 }
 
 #[derive(Clone)]
@@ -3223,6 +4617,24 @@ pub enum TraceOpAggregateLazy<'a> {
         from: GlobalSourceIdLazy<'a>,
         to: GlobalSourceIdLazy<'a>,
     },
+    Panicked {
+        index: u16,
+    },
+    Success {
+        index: u16,
+    },
+    Failure {
+        variant: Option<&'a str>,
+        index: u16,
+    },
+    Cancelled {
+        index: u16,
+    },
+    FutureStart {
+        polls: u16,
+        busy_nanos: u16,
+        idle_nanos: u16,
+    },
 }
 
 impl<'a> Compatible<TraceOpAggregateLazy<'a>> for TraceOpAggregateLazy<'a> { }
@@ -3248,7 +4660,7 @@ impl<'a> Lazy<'a> for TraceOpAggregateLazy<'a> {
 
 #[cfg(any(feature = "std", feature = "alloc"))]
 impl BaseLen for TraceOpAggregate {
-    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32);
+    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6);
 }
 
 impl Encode for TraceOpAggregate {
@@ -3292,6 +4704,21 @@ impl Encode for TraceOpAggregate {
             TraceOpAggregate::GlobalChannelTransfer { from, to } => {
                 from.scratch_len() + to.scratch_len()
             }
+            TraceOpAggregate::Panicked { index } => {
+                index.scratch_len()
+            }
+            TraceOpAggregate::Success { index } => {
+                index.scratch_len()
+            }
+            TraceOpAggregate::Failure { variant, index } => {
+                variant.scratch_len() + index.scratch_len()
+            }
+            TraceOpAggregate::Cancelled { index } => {
+                index.scratch_len()
+            }
+            TraceOpAggregate::FutureStart { polls, busy_nanos, idle_nanos } => {
+                polls.scratch_len() + busy_nanos.scratch_len() + idle_nanos.scratch_len()
+            }
         }
     }
 
@@ -3375,6 +4802,34 @@ impl Encode for TraceOpAggregate {
                 to.encode(cursor);
                 cursor.base(Self::BASE_LEN - 1 - (32)).fill(0);
             }
+            TraceOpAggregate::Panicked { index } => {
+                cursor.base(1)[0] = 16;
+                index.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (2)).fill(0);
+            }
+            TraceOpAggregate::Success { index } => {
+                cursor.base(1)[0] = 17;
+                index.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (2)).fill(0);
+            }
+            TraceOpAggregate::Failure { variant, index } => {
+                cursor.base(1)[0] = 18;
+                variant.encode(cursor);
+                index.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (11)).fill(0);
+            }
+            TraceOpAggregate::Cancelled { index } => {
+                cursor.base(1)[0] = 19;
+                index.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (2)).fill(0);
+            }
+            TraceOpAggregate::FutureStart { polls, busy_nanos, idle_nanos } => {
+                cursor.base(1)[0] = 20;
+                polls.encode(cursor);
+                busy_nanos.encode(cursor);
+                idle_nanos.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (6)).fill(0);
+            }
         }
     }
 }
@@ -3487,13 +4942,54 @@ impl<'a> Decode<'a> for TraceOpAggregate {
                     to,
                 })
             }
+            16 => {
+                let index = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (2));
+                Ok(TraceOpAggregate::Panicked {
+                    index,
+                })
+            }
+            17 => {
+                let index = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (2));
+                Ok(TraceOpAggregate::Success {
+                    index,
+                })
+            }
+            18 => {
+                let variant = Decode::decode(cursor)?;
+                let index = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (11));
+                Ok(TraceOpAggregate::Failure {
+                    variant,
+                    index,
+                })
+            }
+            19 => {
+                let index = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (2));
+                Ok(TraceOpAggregate::Cancelled {
+                    index,
+                })
+            }
+            20 => {
+                let polls = Decode::decode(cursor)?;
+                let busy_nanos = Decode::decode(cursor)?;
+                let idle_nanos = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (6));
+                Ok(TraceOpAggregate::FutureStart {
+                    polls,
+                    busy_nanos,
+                    idle_nanos,
+                })
+            }
             _ => { Err(DecodeError) }
         }
     }
 }
 
 impl<'a> BaseLen for TraceOpAggregateLazy<'a> {
-    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32);
+    const BASE_LEN: usize = 1 + max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(max(0, 0), 0), 8), 0), 0), 2), 2), 8), 0), 10), 8), 8), 16), 16), 16), 32), 2), 2), 11), 2), 6);
 }
 
 impl<'a> Encode for TraceOpAggregateLazy<'a> {
@@ -3537,6 +5033,21 @@ impl<'a> Encode for TraceOpAggregateLazy<'a> {
             TraceOpAggregateLazy::GlobalChannelTransfer { from, to } => {
                 from.scratch_len() + to.scratch_len()
             }
+            TraceOpAggregateLazy::Panicked { index } => {
+                index.scratch_len()
+            }
+            TraceOpAggregateLazy::Success { index } => {
+                index.scratch_len()
+            }
+            TraceOpAggregateLazy::Failure { variant, index } => {
+                variant.scratch_len() + index.scratch_len()
+            }
+            TraceOpAggregateLazy::Cancelled { index } => {
+                index.scratch_len()
+            }
+            TraceOpAggregateLazy::FutureStart { polls, busy_nanos, idle_nanos } => {
+                polls.scratch_len() + busy_nanos.scratch_len() + idle_nanos.scratch_len()
+            }
         }
     }
 
@@ -3620,6 +5131,34 @@ impl<'a> Encode for TraceOpAggregateLazy<'a> {
                 to.encode(cursor);
                 cursor.base(Self::BASE_LEN - 1 - (32)).fill(0);
             }
+            TraceOpAggregateLazy::Panicked { index } => {
+                cursor.base(1)[0] = 16;
+                index.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (2)).fill(0);
+            }
+            TraceOpAggregateLazy::Success { index } => {
+                cursor.base(1)[0] = 17;
+                index.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (2)).fill(0);
+            }
+            TraceOpAggregateLazy::Failure { variant, index } => {
+                cursor.base(1)[0] = 18;
+                variant.encode(cursor);
+                index.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (11)).fill(0);
+            }
+            TraceOpAggregateLazy::Cancelled { index } => {
+                cursor.base(1)[0] = 19;
+                index.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (2)).fill(0);
+            }
+            TraceOpAggregateLazy::FutureStart { polls, busy_nanos, idle_nanos } => {
+                cursor.base(1)[0] = 20;
+                polls.encode(cursor);
+                busy_nanos.encode(cursor);
+                idle_nanos.encode(cursor);
+                cursor.base(Self::BASE_LEN - 1 - (6)).fill(0);
+            }
         }
     }
 }
@@ -3731,6 +5270,47 @@ impl<'a> Decode<'a> for TraceOpAggregateLazy<'a> {
                     to,
                 })
             }
+            16 => {
+                let index = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (2));
+                Ok(TraceOpAggregateLazy::Panicked {
+                    index,
+                })
+            }
+            17 => {
+                let index = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (2));
+                Ok(TraceOpAggregateLazy::Success {
+                    index,
+                })
+            }
+            18 => {
+                let variant = Decode::decode(cursor)?;
+                let index = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (11));
+                Ok(TraceOpAggregateLazy::Failure {
+                    variant,
+                    index,
+                })
+            }
+            19 => {
+                let index = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (2));
+                Ok(TraceOpAggregateLazy::Cancelled {
+                    index,
+                })
+            }
+            20 => {
+                let polls = Decode::decode(cursor)?;
+                let busy_nanos = Decode::decode(cursor)?;
+                let idle_nanos = Decode::decode(cursor)?;
+                cursor.advance(Self::BASE_LEN - 1 - (6));
+                Ok(TraceOpAggregateLazy::FutureStart {
+                    polls,
+                    busy_nanos,
+                    idle_nanos,
+                })
+            }
             _ => { Err(DecodeError) }
         }
     }
@@ -3805,6 +5385,34 @@ impl<'a> TryFrom<TraceOpAggregateLazy<'a>> for TraceOpAggregate {
                     to: Owned::lazy_to_owned(to)?,
                 })
             }
+            TraceOpAggregateLazy::Panicked { index, } => {
+                Ok(TraceOpAggregate::Panicked {
+                    index: Owned::lazy_to_owned(index)?,
+                })
+            }
+            TraceOpAggregateLazy::Success { index, } => {
+                Ok(TraceOpAggregate::Success {
+                    index: Owned::lazy_to_owned(index)?,
+                })
+            }
+            TraceOpAggregateLazy::Failure { variant,index, } => {
+                Ok(TraceOpAggregate::Failure {
+                    variant: Owned::lazy_to_owned(variant)?,
+                    index: Owned::lazy_to_owned(index)?,
+                })
+            }
+            TraceOpAggregateLazy::Cancelled { index, } => {
+                Ok(TraceOpAggregate::Cancelled {
+                    index: Owned::lazy_to_owned(index)?,
+                })
+            }
+            TraceOpAggregateLazy::FutureStart { polls,busy_nanos,idle_nanos, } => {
+                Ok(TraceOpAggregate::FutureStart {
+                    polls: Owned::lazy_to_owned(polls)?,
+                    busy_nanos: Owned::lazy_to_owned(busy_nanos)?,
+                    idle_nanos: Owned::lazy_to_owned(idle_nanos)?,
+                })
+            }
         }
     }
 }
@@ -3939,6 +5547,58 @@ impl<'a> PartialEq for TraceOpAggregateLazy<'a> {
                 self_from == other_from
                     && self_to == other_to
             }
+            (
+                TraceOpAggregateLazy::Panicked {
+                    index: self_index
+                },
+                TraceOpAggregateLazy::Panicked {
+                    index: other_index
+                },
+            ) => {
+                self_index == other_index
+            }
+            (
+                TraceOpAggregateLazy::Success {
+                    index: self_index
+                },
+                TraceOpAggregateLazy::Success {
+                    index: other_index
+                },
+            ) => {
+                self_index == other_index
+            }
+            (
+                TraceOpAggregateLazy::Failure {
+                    variant: self_variant, index: self_index
+                },
+                TraceOpAggregateLazy::Failure {
+                    variant: other_variant, index: other_index
+                },
+            ) => {
+                self_variant == other_variant
+                    && self_index == other_index
+            }
+            (
+                TraceOpAggregateLazy::Cancelled {
+                    index: self_index
+                },
+                TraceOpAggregateLazy::Cancelled {
+                    index: other_index
+                },
+            ) => {
+                self_index == other_index
+            }
+            (
+                TraceOpAggregateLazy::FutureStart {
+                    polls: self_polls, busy_nanos: self_busy_nanos, idle_nanos: self_idle_nanos
+                },
+                TraceOpAggregateLazy::FutureStart {
+                    polls: other_polls, busy_nanos: other_busy_nanos, idle_nanos: other_idle_nanos
+                },
+            ) => {
+                self_polls == other_polls
+                    && self_busy_nanos == other_busy_nanos&& self_idle_nanos == other_idle_nanos
+            }
             #[allow(unreachable_patterns)]
             _ => false,
         }
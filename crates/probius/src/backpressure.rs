@@ -0,0 +1,152 @@
+#[cfg(feature = "enabled")]
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::Thread,
+    time::{Duration, Instant},
+};
+#[cfg(feature = "enabled")]
+use std::sync::Arc;
+
+#[cfg(feature = "enabled")]
+use crate::{DropPolicy, config::BATCH_COUNT};
+
+/// What an instance's `DropPolicy` has done since it was initialized, as reported by
+/// `Probius::backpressure_stats`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BackpressureStats {
+    /// Events discarded because no buffer could be found for them.
+    pub dropped_events: u64,
+    /// Completed buffers discarded under `DropPolicy::DropOldest` to make room for new events.
+    pub dropped_buffers: u64,
+    /// Writes that waited for a free buffer under `DropPolicy::Block`.
+    pub blocked_writes: u64,
+    /// Total time spent waiting under `DropPolicy::Block`.
+    pub blocked_nanos: u64,
+    /// Buffers added to the pool under `DropPolicy::Grow`.
+    pub grown_buffers: u64,
+}
+
+/// An instance's `DropPolicy` along with the state it needs, shared by all of its writers.
+#[cfg(feature = "enabled")]
+pub(crate) struct Backpressure {
+    pub policy: DropPolicy,
+    // The configured pool first, followed by any added under `DropPolicy::Grow`.
+    pools: Mutex<Vec<bab::HeapBufferPool>>,
+    grow_step: usize,
+    total_buffer_count: AtomicU64,
+    dropped_events: AtomicU64,
+    dropped_buffers: AtomicU64,
+    blocked_writes: AtomicU64,
+    blocked_nanos: AtomicU64,
+    grown_buffers: AtomicU64,
+}
+
+#[cfg(feature = "enabled")]
+impl Backpressure {
+    pub fn new(policy: DropPolicy, buffer_pool: bab::HeapBufferPool) -> Self {
+        let buffer_count = buffer_pool.total_buffer_count();
+        Self {
+            policy,
+            pools: Mutex::new(vec![buffer_pool]),
+            grow_step: buffer_count,
+            total_buffer_count: AtomicU64::new(buffer_count as u64),
+            dropped_events: AtomicU64::new(0),
+            dropped_buffers: AtomicU64::new(0),
+            blocked_writes: AtomicU64::new(0),
+            blocked_nanos: AtomicU64::new(0),
+            grown_buffers: AtomicU64::new(0),
+        }
+    }
+
+    pub fn buffer_pool(&self) -> bab::HeapBufferPool {
+        self.lock_pools()[0].clone()
+    }
+
+    /// The pools that writers can take buffers from, the configured one first.
+    pub fn pools(&self) -> Vec<bab::HeapBufferPool> {
+        self.lock_pools().clone()
+    }
+
+    /// The number of buffers in all of the pools.
+    pub fn buffer_count(&self) -> u64 {
+        self.total_buffer_count.load(Ordering::Relaxed)
+    }
+
+    /// Add a pool as large as the configured one, unless that would take the total number of
+    /// buffers past `max_buffer_count`.
+    pub fn grow(&self, max_buffer_count: usize) -> Option<bab::HeapBufferPool> {
+        let mut pools = self.lock_pools();
+        let buffers_per_batch = self.grow_step.div_ceil(BATCH_COUNT);
+        let added_count = buffers_per_batch * BATCH_COUNT;
+        let total_count = self.total_buffer_count.load(Ordering::Relaxed) as usize;
+        if total_count + added_count > max_buffer_count {
+            return None;
+        }
+
+        let buffer_size = pools[0].buffer_size();
+        let pool = bab::HeapBufferPool::new(buffer_size, BATCH_COUNT, buffers_per_batch);
+        pools.push(pool.clone());
+        self.total_buffer_count.fetch_add(added_count as u64, Ordering::Relaxed);
+        self.grown_buffers.fetch_add(added_count as u64, Ordering::Relaxed);
+        Some(pool)
+    }
+
+    pub fn record_dropped_event(&self) {
+        self.dropped_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_dropped_buffer(&self) {
+        self.dropped_buffers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_blocked_write(&self, duration: Duration) {
+        self.blocked_writes.fetch_add(1, Ordering::Relaxed);
+        self.blocked_nanos.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> BackpressureStats {
+        BackpressureStats {
+            dropped_events: self.dropped_events.load(Ordering::Relaxed),
+            dropped_buffers: self.dropped_buffers.load(Ordering::Relaxed),
+            blocked_writes: self.blocked_writes.load(Ordering::Relaxed),
+            blocked_nanos: self.blocked_nanos.load(Ordering::Relaxed),
+            grown_buffers: self.grown_buffers.load(Ordering::Relaxed),
+        }
+    }
+
+    fn lock_pools(&self) -> std::sync::MutexGuard<'_, Vec<bab::HeapBufferPool>> {
+        self.pools.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Poll `future` on the calling thread until it completes or `timeout` passes.
+#[cfg(feature = "enabled")]
+pub(crate) fn block_on_timeout<F: Future>(future: F, timeout: Duration) -> Option<F::Output> {
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let deadline = Instant::now() + timeout;
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = core::pin::pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Some(output);
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return None;
+        }
+        // Buffers freed on other threads don't always wake waiters, so poll again regularly.
+        std::thread::park_timeout(remaining.min(Duration::from_millis(1)));
+    }
+}
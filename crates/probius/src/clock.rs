@@ -0,0 +1,428 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+use std::time::Duration;
+
+use crate::ClockSource;
+
+/// How often the cycle counter is timed against the monotonic clock again.
+const RECALIBRATION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The monotonic clock of a session. Every source of a `Probius` instance stamps its events with
+/// the nanoseconds since the instance was initialized, so events from different sources and
+/// threads can be ordered against each other.
+///
+/// Every `anchor_interval`, one of the writers also writes a `ClockAnchor` event, which pairs the
+/// clock with the wall clock so that collectors can line events up with logs. The wall clock may
+/// jump, so collectors should use the latest anchor rather than the first.
+#[derive(Clone)]
+pub(crate) struct SessionClock {
+    inner: Arc<SessionClockInner>,
+}
+
+struct SessionClockInner {
+    start: platform::Instant,
+    // Read instead of `start` if the session uses `ClockSource::CycleCounter` and it's available.
+    cycles: Option<cycle_counter::CycleClock>,
+    next_calibration_nanos: AtomicU64,
+    anchor_interval_nanos: u64,
+    // Writers race to claim each anchor, so that only one of them writes it.
+    next_anchor_nanos: AtomicU64,
+}
+
+impl SessionClock {
+    pub fn new(source: ClockSource, anchor_interval: Duration) -> Self {
+        Self {
+            inner: Arc::new(SessionClockInner {
+                start: platform::Instant::now(),
+                cycles: match source {
+                    ClockSource::Monotonic => None,
+                    ClockSource::CycleCounter => cycle_counter::CycleClock::new(),
+                },
+                next_calibration_nanos: AtomicU64::new(RECALIBRATION_INTERVAL.as_nanos() as u64),
+                anchor_interval_nanos: anchor_interval.as_nanos() as u64,
+                next_anchor_nanos: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// The source that this clock reads, which is `Monotonic` if the cycle counter was asked for
+    /// but isn't available.
+    pub fn source(&self) -> ClockSource {
+        match self.inner.cycles {
+            Some(_) => ClockSource::CycleCounter,
+            None => ClockSource::Monotonic,
+        }
+    }
+
+    #[inline]
+    pub fn now_nanos(&self) -> u64 {
+        match &self.inner.cycles {
+            Some(cycles) => cycles.now_nanos(),
+            None => self.inner.start.elapsed_nanos(),
+        }
+    }
+
+    /// Whether the caller, which read the clock at `now_nanos`, should write the next anchor.
+    /// Returns true to at most one caller per interval.
+    #[inline]
+    pub fn claim_anchor(&self, now_nanos: u64) -> bool {
+        claim(&self.inner.next_anchor_nanos, now_nanos, self.inner.anchor_interval_nanos)
+    }
+
+    /// Time the cycle counter against the monotonic clock again if it's due, and no other caller
+    /// has claimed it.
+    #[inline]
+    pub fn recalibrate_if_due(&self, now_nanos: u64) {
+        if let Some(cycles) = &self.inner.cycles {
+            let interval_nanos = RECALIBRATION_INTERVAL.as_nanos() as u64;
+            if claim(&self.inner.next_calibration_nanos, now_nanos, interval_nanos) {
+                cycles.recalibrate();
+            }
+        }
+    }
+
+    /// Read this clock and the wall clock together.
+    pub fn anchor(&self) -> probius_mproto::ClockAnchor {
+        let unix_nanos = platform::unix_nanos();
+        probius_mproto::ClockAnchor { monotonic_nanos: self.now_nanos(), unix_nanos }
+    }
+}
+
+/// Whether the caller, which read the clock at `now_nanos`, claimed the periodic task scheduled by
+/// `next_nanos`, moving it `interval_nanos` on.
+#[inline]
+fn claim(next_nanos: &AtomicU64, now_nanos: u64, interval_nanos: u64) -> bool {
+    let next = next_nanos.load(Ordering::Relaxed);
+    if now_nanos < next {
+        return false;
+    }
+    let claimed = now_nanos.saturating_add(interval_nanos);
+    next_nanos.compare_exchange(next, claimed, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+}
+
+// Each platform provides a monotonic `Instant` and the wall clock.
+//
+// std's clocks work everywhere but on `wasm32-unknown-unknown`, where they panic. There, browsers
+// are read through `performance.now()` and `Date.now()` with the `web-clock` feature, whose
+// resolution browsers coarsen to somewhere between 5 microseconds and a millisecond. Without it,
+// every timestamp is 0.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod platform {
+    pub struct Instant(std::time::Instant);
+
+    impl Instant {
+        pub fn now() -> Self {
+            Self(std::time::Instant::now())
+        }
+
+        #[inline]
+        pub fn elapsed_nanos(&self) -> u64 {
+            self.0.elapsed().as_nanos() as u64
+        }
+    }
+
+    pub fn unix_nanos() -> u64 {
+        let now = std::time::SystemTime::now();
+        now.duration_since(std::time::SystemTime::UNIX_EPOCH).unwrap_or_default().as_nanos() as u64
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", feature = "web-clock"))]
+mod platform {
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = performance, js_name = now)]
+        fn performance_now() -> f64;
+
+        #[wasm_bindgen(js_namespace = Date, js_name = now)]
+        fn date_now() -> f64;
+    }
+
+    /// Milliseconds since the page or worker started.
+    pub struct Instant(f64);
+
+    impl Instant {
+        pub fn now() -> Self {
+            Self(performance_now())
+        }
+
+        #[inline]
+        pub fn elapsed_nanos(&self) -> u64 {
+            ((performance_now() - self.0).max(0.0) * 1e6) as u64
+        }
+    }
+
+    pub fn unix_nanos() -> u64 {
+        (date_now() * 1e6) as u64
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", not(feature = "web-clock")))]
+mod platform {
+    pub struct Instant;
+
+    impl Instant {
+        pub fn now() -> Self {
+            Self
+        }
+
+        #[inline]
+        pub fn elapsed_nanos(&self) -> u64 {
+            0
+        }
+    }
+
+    pub fn unix_nanos() -> u64 {
+        0
+    }
+}
+
+// The cycle counter: the TSC on x86_64 and the virtual counter on aarch64. Linux's cpuinfo flags
+// tell whether the TSC ticks at a constant rate and keeps ticking in sleep states, so that it
+// measures time rather than work. The aarch64 counter always does.
+//
+// Reading it costs a few nanoseconds, against tens for `Instant::now()` on some machines and
+// clock sources.
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod cycle_counter {
+    use std::sync::atomic::{AtomicU64, Ordering, fence};
+    use std::time::{Duration, Instant};
+
+    /// How long the cycle counter is timed against `Instant` when the session starts.
+    const INITIAL_CALIBRATION: Duration = Duration::from_millis(1);
+    /// `nanos_per_tick` is a fixed-point number with this many fractional bits.
+    const FRACTION_BITS: u32 = 32;
+    /// How many times the cycle counter and `Instant` are read together for each calibration
+    /// point, keeping the pair that was read closest together.
+    const CALIBRATION_SAMPLES: u32 = 5;
+
+    /// Nanoseconds since the session started, measured by the cycle counter at a rate that is
+    /// timed against `Instant` over the whole session so far.
+    pub struct CycleClock {
+        start: Instant,
+        start_ticks: u64,
+        // A seqlock around the calibration below, which is odd while `recalibrate` updates it.
+        seq: AtomicU64,
+        base_ticks: AtomicU64,
+        base_nanos: AtomicU64,
+        nanos_per_tick: AtomicU64,
+    }
+
+    impl CycleClock {
+        /// Calibrate the cycle counter, or return `None` if it can't be used to measure time.
+        pub fn new() -> Option<Self> {
+            if !is_invariant() {
+                return None;
+            }
+            let (start_ticks, start) = read_both();
+            let (ticks, elapsed) = loop {
+                let (ticks, now) = read_both();
+                let elapsed = now.duration_since(start);
+                if elapsed >= INITIAL_CALIBRATION {
+                    break (ticks, elapsed);
+                }
+                std::hint::spin_loop();
+            };
+            let nanos_per_tick = nanos_per_tick(ticks.wrapping_sub(start_ticks), elapsed)?;
+            Some(Self {
+                start,
+                start_ticks,
+                seq: AtomicU64::new(0),
+                base_ticks: AtomicU64::new(start_ticks),
+                base_nanos: AtomicU64::new(0),
+                nanos_per_tick: AtomicU64::new(nanos_per_tick),
+            })
+        }
+
+        #[inline]
+        pub fn now_nanos(&self) -> u64 {
+            loop {
+                let seq = self.seq.load(Ordering::Acquire);
+                let base_ticks = self.base_ticks.load(Ordering::Relaxed);
+                let base_nanos = self.base_nanos.load(Ordering::Relaxed);
+                let nanos_per_tick = self.nanos_per_tick.load(Ordering::Relaxed);
+                fence(Ordering::Acquire);
+                if seq.is_multiple_of(2) && self.seq.load(Ordering::Relaxed) == seq {
+                    return base_nanos + ticks_to_nanos(read_ticks(), base_ticks, nanos_per_tick);
+                }
+                std::hint::spin_loop();
+            }
+        }
+
+        /// Time the cycle counter against `Instant` again, which makes it more precise the longer
+        /// the session runs. Callers must take turns.
+        pub fn recalibrate(&self) {
+            let (ticks, now) = read_both();
+            let elapsed = now.duration_since(self.start);
+            let Some(nanos_per_tick) = nanos_per_tick(ticks.wrapping_sub(self.start_ticks), elapsed)
+            else {
+                return;
+            };
+
+            // Continue from where the old calibration got to, catching up with `Instant` if it
+            // fell behind, so that timestamps never go backwards.
+            let base_nanos = self.base_nanos.load(Ordering::Relaxed) + ticks_to_nanos(
+                ticks,
+                self.base_ticks.load(Ordering::Relaxed),
+                self.nanos_per_tick.load(Ordering::Relaxed),
+            );
+            let base_nanos = base_nanos.max(elapsed.as_nanos() as u64);
+
+            let seq = self.seq.load(Ordering::Relaxed);
+            self.seq.store(seq + 1, Ordering::Relaxed);
+            fence(Ordering::Release);
+            self.base_ticks.store(ticks, Ordering::Relaxed);
+            self.base_nanos.store(base_nanos, Ordering::Relaxed);
+            self.nanos_per_tick.store(nanos_per_tick, Ordering::Relaxed);
+            self.seq.store(seq + 2, Ordering::Release);
+        }
+    }
+
+    /// Read the cycle counter and `Instant` at as nearly the same moment as possible. Each
+    /// `Instant` is read between two reads of the counter, and a thread preempted in between
+    /// leaves a wide gap, so the narrowest of several tries is kept.
+    fn read_both() -> (u64, Instant) {
+        let mut best: Option<(u64, u64, Instant)> = None;
+        for _ in 0..CALIBRATION_SAMPLES {
+            let before = read_ticks();
+            let now = Instant::now();
+            let gap = read_ticks().wrapping_sub(before);
+            if best.is_none_or(|(best_gap, _, _)| gap < best_gap) {
+                best = Some((gap, before.wrapping_add(gap / 2), now));
+            }
+        }
+        let (_, ticks, now) = best.expect("at least one sample");
+        (ticks, now)
+    }
+
+    fn nanos_per_tick(ticks: u64, elapsed: Duration) -> Option<u64> {
+        let nanos_per_tick = (elapsed.as_nanos() << FRACTION_BITS).checked_div(ticks as u128)?;
+        u64::try_from(nanos_per_tick).ok().filter(|&n| n > 0)
+    }
+
+    #[inline]
+    fn ticks_to_nanos(ticks: u64, base_ticks: u64, nanos_per_tick: u64) -> u64 {
+        // Counters read on different cores may be a few ticks apart.
+        let ticks = ticks.saturating_sub(base_ticks);
+        ((ticks as u128 * nanos_per_tick as u128) >> FRACTION_BITS) as u64
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[inline]
+    fn read_ticks() -> u64 {
+        let (lo, hi): (u32, u32);
+        unsafe {
+            core::arch::asm!(
+                "rdtsc",
+                out("eax") lo,
+                out("edx") hi,
+                options(nomem, nostack, preserves_flags),
+            );
+        }
+        (hi as u64) << 32 | lo as u64
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[inline]
+    fn read_ticks() -> u64 {
+        let ticks: u64;
+        unsafe {
+            core::arch::asm!(
+                "isb",
+                "mrs {}, cntvct_el0",
+                out(reg) ticks,
+                options(nomem, nostack, preserves_flags),
+            );
+        }
+        ticks
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn is_invariant() -> bool {
+        let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo") else {
+            return false;
+        };
+        let Some(flags) = cpuinfo.lines().find(|line| line.starts_with("flags")) else {
+            return false;
+        };
+        let mut flags = flags.split_whitespace();
+        flags.clone().any(|flag| flag == "constant_tsc") && flags.any(|flag| flag == "nonstop_tsc")
+    }
+
+    /// The generic timer ticks at a constant rate on every core.
+    #[cfg(target_arch = "aarch64")]
+    fn is_invariant() -> bool {
+        true
+    }
+}
+
+#[cfg(not(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+mod cycle_counter {
+    pub enum CycleClock { }
+
+    impl CycleClock {
+        pub fn new() -> Option<Self> {
+            None
+        }
+
+        pub fn now_nanos(&self) -> u64 {
+            match *self { }
+        }
+
+        pub fn recalibrate(&self) {
+            match *self { }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_claim_anchor() {
+        let clock = SessionClock::new(ClockSource::Monotonic, Duration::from_secs(1));
+        assert!(clock.claim_anchor(0));
+        assert!(!clock.claim_anchor(0));
+        assert!(!clock.claim_anchor(999_999_999));
+        assert!(clock.claim_anchor(1_500_000_000));
+        assert!(!clock.claim_anchor(2_000_000_000));
+        assert!(clock.claim_anchor(2_500_000_000));
+    }
+
+    #[test]
+    fn test_cycle_counter() {
+        let clock = SessionClock::new(ClockSource::CycleCounter, Duration::from_secs(1));
+
+        // Wherever it's read from, the clock keeps up with `Instant` and never goes backwards,
+        // including across recalibrations. Each `Instant` is read between two readings of the
+        // clock, which bound it even if this thread is preempted in between.
+        let before_start = clock.now_nanos();
+        let start = std::time::Instant::now();
+        let after_start = clock.now_nanos();
+        let mut last_nanos = after_start;
+        for i in 0..5 {
+            std::thread::sleep(Duration::from_millis(20));
+            if let Some(cycles) = &clock.inner.cycles {
+                cycles.recalibrate();
+            }
+            let now_nanos = clock.now_nanos();
+            assert!(now_nanos >= last_nanos, "went backwards at {i}");
+            last_nanos = now_nanos;
+        }
+        let before_end = last_nanos;
+        let elapsed = start.elapsed().as_nanos() as u64;
+        let after_end = clock.now_nanos();
+        let (min, max) = (before_end - after_start, after_end - before_start);
+        assert!(
+            elapsed >= min - min / 20 && elapsed <= max + max / 20,
+            "{elapsed} vs {min}..{max}",
+        );
+    }
+}
@@ -2,22 +2,30 @@
 use core::{
     cell::Cell,
     future::{Future, poll_fn},
-    pin::{Pin, pin},
+    pin::pin,
     ptr::NonNull,
 };
+#[cfg(feature = "enabled")]
+use std::sync::{Arc, OnceLock};
 
 use crate::{Source, SourceId};
 
 #[cfg(feature = "enabled")]
-use crate::Probius;
+use crate::{filter, trace::LocalProbius};
 
 #[cfg(feature = "enabled")]
 thread_local! {
-    static CURRENT_COMPONENT: Cell<Option<NonNull<()>>> = Cell::new(None);
+    static CURRENT_COMPONENT: Cell<Option<NonNull<ComponentContext>>> = Cell::new(None);
 }
 
 pub struct Component {
+    #[cfg(not(feature = "enabled"))]
     source: Source,
+    // `None` if the component was filtered out when it was created.
+    #[cfg(feature = "enabled")]
+    source: Option<Source>,
+    #[cfg(feature = "enabled")]
+    context: ComponentContext,
 }
 
 impl Component {
@@ -29,28 +37,50 @@ impl Component {
     }
 
     #[cfg(feature = "enabled")]
-    pub(crate) fn new(probius: Probius, name: &str, is_recurring: bool) -> Self {
-        Self {
-            source: Source::new(probius, name, is_recurring),
-        }
+    pub(crate) fn new(probius: LocalProbius, name: &str, is_recurring: bool) -> Self {
+        let path = Arc::new(SourcePath::new(name));
+        let source = filter::is_path_enabled(&path)
+            .then(|| Source::new(probius, name, is_recurring));
+        // A filtered out component can still be entered, so that the filter sees it in the paths
+        // of the sources in it, but they are parented to the enclosing component instead.
+        let context = ComponentContext {
+            component: source.as_ref().map(Source::id).or_else(current_id),
+            path: Some(path),
+        };
+        Self { source, context }
     }
 
+    #[cfg(not(feature = "enabled"))]
     pub fn id(&self) -> SourceId {
         self.source.id()
     }
 
+    /// This component's source, or `u64::MAX` if it was filtered out.
+    #[cfg(feature = "enabled")]
+    pub fn id(&self) -> SourceId {
+        self.source.as_ref().map_or(SourceId { source: u64::MAX }, Source::id)
+    }
+
+    /// Get a `Send`able handle that can be used to enter this component from other threads and
+    /// tasks.
+    #[cfg(feature = "enabled")]
+    pub fn context(&self) -> ComponentContext {
+        self.context.clone()
+    }
+
+    #[cfg(not(feature = "enabled"))]
+    pub fn context(&self) -> ComponentContext {
+        ComponentContext(())
+    }
+
     #[cfg(feature = "enabled")]
     #[inline]
     pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
-        // self cannot move for the duration of this method.
-        let self_pin = Pin::new(self);
-
-        let parent = CURRENT_COMPONENT.replace(Some(NonNull::from(&*self_pin).cast()));
-        let result = f();
-        CURRENT_COMPONENT.set(parent);
-        result
+        self.context.enter(f)
     }
 
+    /// Poll `f` in this component. Components have no aggregate graph, so this records no poll
+    /// statistics - trace the returned future with `TraceSource::trace_future` to get them.
     #[cfg(feature = "enabled")]
     #[inline]
     pub async fn enter_async<F: Future>(&self, f: F) -> F::Output {
@@ -76,11 +106,168 @@ impl Component {
     }
 }
 
+/// A position in the component tree that can be sent to other threads and re-entered there, so
+/// that sources created by spawned threads and tasks are parented correctly.
+///
+/// Sources created in a context whose component has since been dropped still name that component
+/// as their parent.
+#[cfg(feature = "enabled")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ComponentContext {
+    component: Option<SourceId>,
+    path: Option<Arc<SourcePath>>,
+}
+
+#[cfg(not(feature = "enabled"))]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ComponentContext(());
+
+impl ComponentContext {
+    /// Capture the component that the calling thread is currently in.
+    #[cfg(feature = "enabled")]
+    #[inline]
+    pub fn current() -> Self {
+        with_current(|context| context.cloned()).unwrap_or_default()
+    }
+
+    #[cfg(not(feature = "enabled"))]
+    #[inline]
+    pub fn current() -> Self {
+        Self(())
+    }
+
+    #[cfg(feature = "enabled")]
+    #[inline]
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        let parent = CURRENT_COMPONENT.replace(Some(NonNull::from(self)));
+        // Restore the parent component even if `f` panics.
+        let _guard = ComponentEnterGuard { parent };
+        f()
+    }
+
+    #[cfg(feature = "enabled")]
+    #[inline]
+    pub async fn enter_async<F: Future>(self, f: F) -> F::Output {
+        let mut f = pin!(f);
+        poll_fn(|cx| {
+            self.enter(|| {
+                f.as_mut().poll(cx)
+            })
+        })
+        .await
+    }
+
+    #[cfg(not(feature = "enabled"))]
+    #[inline]
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    #[cfg(not(feature = "enabled"))]
+    #[inline]
+    pub async fn enter_async<F: Future>(self, f: F) -> F::Output {
+        f.await
+    }
+}
+
+/// Spawn a thread that runs `f` in the calling thread's current component.
+pub fn spawn<F, T>(f: F) -> std::thread::JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+{
+    let context = ComponentContext::current();
+    std::thread::spawn(move || context.enter(f))
+}
+
+/// Wrap `f` so that it is always polled in the calling thread's current component. Use this to
+/// carry the component along when handing a future to an executor's `spawn`.
+pub fn in_current_component<F: Future>(f: F) -> impl Future<Output = F::Output> {
+    ComponentContext::current().enter_async(f)
+}
+
+#[cfg(feature = "enabled")]
+struct ComponentEnterGuard {
+    parent: Option<NonNull<ComponentContext>>,
+}
+
+#[cfg(feature = "enabled")]
+impl Drop for ComponentEnterGuard {
+    #[inline]
+    fn drop(&mut self) {
+        CURRENT_COMPONENT.set(self.parent);
+    }
+}
+
+#[cfg(feature = "enabled")]
+#[inline]
+fn with_current<R>(f: impl FnOnce(Option<&ComponentContext>) -> R) -> R {
+    // The current context is borrowed by an active `ComponentContext::enter` further up the stack.
+    let context = CURRENT_COMPONENT.get().map(|ptr| unsafe { ptr.as_ref() });
+    f(context)
+}
+
 #[cfg(feature = "enabled")]
 #[inline]
-pub(crate) fn with_current<R>(f: impl FnOnce(Option<&Component>) -> R) -> R {
-    let component = CURRENT_COMPONENT.get()
-        .map(|ptr| unsafe { ptr.cast().as_ref() });
-    f(component)
+pub(crate) fn current_id() -> Option<SourceId> {
+    with_current(|context| context.and_then(|c| c.component))
+}
+
+/// The names of a source and its enclosing components. They're only joined into a path by `get`,
+/// which is only needed while a filter is set.
+#[cfg(feature = "enabled")]
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct SourcePath {
+    parent: Option<Arc<SourcePath>>,
+    name: Box<str>,
+    joined: OnceLock<Box<str>>,
+}
+
+#[cfg(feature = "enabled")]
+impl SourcePath {
+    /// The path of a source named `name` created in the current component.
+    pub fn new(name: &str) -> Self {
+        Self {
+            parent: with_current(|context| context.and_then(|c| c.path.clone())),
+            name: name.into(),
+            joined: OnceLock::new(),
+        }
+    }
+
+    /// The names joined by '/'.
+    pub fn get(&self) -> &str {
+        let Some(parent) = &self.parent else {
+            return &self.name;
+        };
+        self.joined.get_or_init(|| format!("{}/{}", parent.get(), self.name).into())
+    }
 }
 
+#[cfg(all(test, feature = "enabled"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_component_context_across_threads() {
+        let probius = crate::trace::test::test_probius();
+        let component = Component::new(probius, "test-component", true);
+        let id = component.id();
+
+        let (thread_id, task_id) = component.enter(|| {
+            let thread = spawn(current_id);
+            let task = std::thread::spawn({
+                let f = in_current_component(async { current_id() });
+                move || pollster::block_on(f)
+            });
+            (thread.join().unwrap(), task.join().unwrap())
+        });
+        assert_eq!(thread_id, Some(id));
+        assert_eq!(task_id, Some(id));
+        assert_eq!(current_id(), None);
+
+        let child_path = component.enter(|| {
+            spawn(|| SourcePath::new("child").get().to_string()).join().unwrap()
+        });
+        assert_eq!(child_path, "test-component/child");
+    }
+}
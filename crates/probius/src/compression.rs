@@ -0,0 +1,271 @@
+use crate::FrameCompression;
+
+/// Matches shorter than this are stored as literals.
+const MIN_MATCH: usize = 4;
+#[cfg(any(feature = "tcp-sink", feature = "file-sink"))]
+const MAX_OFFSET: usize = u16::MAX as usize;
+#[cfg(any(feature = "tcp-sink", feature = "file-sink"))]
+const HASH_BITS: u32 = 12;
+/// Frames are never longer than this, so neither is a decompressed payload.
+const MAX_DECOMPRESSED_LEN: usize = u16::MAX as usize;
+
+// Every frame of a compressed stream starts with a byte saying how the rest of it is encoded:
+// the encoded `FrameCompression::Uncompressed` if the payload is stored as is, because compressing
+// it didn't make it any smaller, or `FrameCompression::Lz`.
+const TAG_UNCOMPRESSED: u8 = 0;
+const TAG_LZ: u8 = 1;
+
+/// Frames buffers for the TCP and file sinks: fills in each buffer's u16 length prefix, after
+/// compressing its payload if the stream is compressed.
+#[cfg(any(feature = "tcp-sink", feature = "file-sink"))]
+pub(crate) struct FrameEncoder {
+    compression: FrameCompression,
+    // Most recent position of each hashed 4-byte sequence, plus one so that 0 means none.
+    table: Vec<u32>,
+    out: Vec<u8>,
+}
+
+#[cfg(any(feature = "tcp-sink", feature = "file-sink"))]
+impl FrameEncoder {
+    pub fn new(compression: FrameCompression) -> Self {
+        Self { compression, table: Vec::new(), out: Vec::new() }
+    }
+
+    pub fn compression(&self) -> FrameCompression {
+        self.compression
+    }
+
+    /// The most bytes that `encode` can return for a `frame_len` byte frame. Payloads that don't
+    /// compress are stored as is, after the byte that says so.
+    #[cfg(feature = "file-sink")]
+    pub fn max_encoded_len(&self, frame_len: usize) -> usize {
+        match self.compression {
+            FrameCompression::Uncompressed => frame_len,
+            FrameCompression::Lz => frame_len + 1,
+        }
+    }
+
+    /// Frame `frame`, which starts with 2 bytes of headroom for the length prefix. Returns the
+    /// bytes to write, which are either `frame` itself or the compressed frame.
+    pub fn encode<'a>(&'a mut self, frame: &'a mut [u8]) -> &'a [u8] {
+        let payload_len = frame.len() - 2;
+        if self.compression == FrameCompression::Uncompressed {
+            frame[..2].copy_from_slice(&(payload_len as u16).to_le_bytes());
+            return frame;
+        }
+
+        self.out.clear();
+        self.out.extend_from_slice(&[0, 0, TAG_LZ]);
+        compress(&frame[2..], &mut self.table, &mut self.out);
+        if self.out.len() - 2 > payload_len {
+            self.out.truncate(2);
+            self.out.push(TAG_UNCOMPRESSED);
+            self.out.extend_from_slice(&frame[2..]);
+        }
+        let frame_len = self.out.len() - 2;
+        self.out[..2].copy_from_slice(&(frame_len as u16).to_le_bytes());
+        &self.out
+    }
+}
+
+/// The events in `frame`, a frame of a stream with the given `compression`, decompressing them
+/// into `buf` if need be.
+pub(crate) fn decode_frame<'a>(
+    compression: FrameCompression,
+    frame: &'a [u8],
+    buf: &'a mut Vec<u8>,
+) -> Option<&'a [u8]> {
+    if compression == FrameCompression::Uncompressed {
+        return Some(frame);
+    }
+    let (tag, payload) = frame.split_first()?;
+    match *tag {
+        TAG_UNCOMPRESSED => Some(payload),
+        TAG_LZ => {
+            buf.clear();
+            decompress(payload, buf)?;
+            Some(buf)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(any(feature = "tcp-sink", feature = "file-sink"))]
+fn hash(bytes: &[u8]) -> usize {
+    let word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    (word.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Append the compressed form of `input` to `out`.
+///
+/// The output is a series of sequences, each a token byte followed by literals and a match:
+///
+/// - The token's high nibble is the number of literals and its low nibble is the match length
+///   minus `MIN_MATCH`. A nibble of 15 is followed by bytes that are added to it, up to and
+///   including the first byte that isn't 255.
+/// - The literals are copied to the output as is.
+/// - The match is a u16 offset back into the output, followed by the extra bytes of the match
+///   length, if any. The last sequence ends after its literals and has no match.
+#[cfg(any(feature = "tcp-sink", feature = "file-sink"))]
+fn compress(input: &[u8], table: &mut Vec<u32>, out: &mut Vec<u8>) {
+    table.clear();
+    table.resize(1 << HASH_BITS, 0);
+
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i + MIN_MATCH <= input.len() {
+        let h = hash(&input[i..]);
+        let candidate = table[h] as usize;
+        table[h] = i as u32 + 1;
+
+        let Some(candidate) = candidate.checked_sub(1) else {
+            i += 1;
+            continue;
+        };
+        let is_match = i - candidate <= MAX_OFFSET
+            && input[candidate..candidate + MIN_MATCH] == input[i..i + MIN_MATCH];
+        if !is_match {
+            i += 1;
+            continue;
+        }
+
+        let mut match_len = MIN_MATCH;
+        while i + match_len < input.len() && input[candidate + match_len] == input[i + match_len] {
+            match_len += 1;
+        }
+
+        write_sequence(out, &input[literal_start..i], Some((i - candidate, match_len)));
+        i += match_len;
+        literal_start = i;
+    }
+    write_sequence(out, &input[literal_start..], None);
+}
+
+#[cfg(any(feature = "tcp-sink", feature = "file-sink"))]
+fn write_sequence(out: &mut Vec<u8>, literals: &[u8], matched: Option<(usize, usize)>) {
+    let match_extra = matched.map_or(0, |(_, len)| len - MIN_MATCH);
+    out.push((literals.len().min(15) as u8) << 4 | match_extra.min(15) as u8);
+    if literals.len() >= 15 {
+        write_extra_len(out, literals.len() - 15);
+    }
+    out.extend_from_slice(literals);
+
+    if let Some((offset, _)) = matched {
+        out.extend_from_slice(&(offset as u16).to_le_bytes());
+        if match_extra >= 15 {
+            write_extra_len(out, match_extra - 15);
+        }
+    }
+}
+
+#[cfg(any(feature = "tcp-sink", feature = "file-sink"))]
+fn write_extra_len(out: &mut Vec<u8>, mut len: usize) {
+    while len >= 255 {
+        out.push(255);
+        len -= 255;
+    }
+    out.push(len as u8);
+}
+
+/// Append the decompressed form of `input` to `out`, or return `None` if it's malformed.
+fn decompress(mut input: &[u8], out: &mut Vec<u8>) -> Option<()> {
+    while let Some((&token, rest)) = input.split_first() {
+        input = rest;
+
+        let mut literal_len = (token >> 4) as usize;
+        if literal_len == 15 {
+            literal_len += read_extra_len(&mut input)?;
+        }
+        let literals = input.get(..literal_len)?;
+        input = &input[literal_len..];
+        if out.len() + literal_len > MAX_DECOMPRESSED_LEN {
+            return None;
+        }
+        out.extend_from_slice(literals);
+
+        let Some((offset, rest)) = input.split_first_chunk::<2>() else {
+            // The last sequence has no match.
+            return input.is_empty().then_some(());
+        };
+        input = rest;
+        let offset = u16::from_le_bytes(*offset) as usize;
+        let mut match_len = (token & 0xf) as usize;
+        if match_len == 15 {
+            match_len += read_extra_len(&mut input)?;
+        }
+        match_len += MIN_MATCH;
+
+        if offset == 0 || offset > out.len() || out.len() + match_len > MAX_DECOMPRESSED_LEN {
+            return None;
+        }
+        // Matches may overlap the bytes they produce, so copy them one at a time.
+        for _ in 0..match_len {
+            out.push(out[out.len() - offset]);
+        }
+    }
+    Some(())
+}
+
+fn read_extra_len(input: &mut &[u8]) -> Option<usize> {
+    let mut len = 0;
+    loop {
+        let (&byte, rest) = input.split_first()?;
+        *input = rest;
+        len += byte as usize;
+        if byte != 255 {
+            return Some(len);
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "tcp-sink", feature = "file-sink")))]
+mod test {
+    use super::*;
+
+    fn round_trip(payload: &[u8]) -> usize {
+        let mut frame = vec![0u8; 2];
+        frame.extend_from_slice(payload);
+        let mut encoder = FrameEncoder::new(FrameCompression::Lz);
+        let encoded = encoder.encode(&mut frame).to_vec();
+
+        let frame_len = u16::from_le_bytes([encoded[0], encoded[1]]) as usize;
+        assert_eq!(frame_len, encoded.len() - 2);
+        let mut buf = Vec::new();
+        let decoded = decode_frame(FrameCompression::Lz, &encoded[2..], &mut buf).unwrap();
+        assert_eq!(decoded, payload);
+        encoded.len()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(b"");
+        round_trip(b"abc");
+        round_trip(&[7; 1000]);
+
+        // Incompressible payloads are stored as is, at the cost of one byte.
+        let random: Vec<u8> = (0..4000).map(|_| fastrand::u8(..)).collect();
+        assert_eq!(round_trip(&random), 2 + 1 + random.len());
+
+        // Repetitive payloads, like aggregates' labels and metrics, shrink by an order of
+        // magnitude.
+        let mut repetitive = Vec::new();
+        for i in 0..200u64 {
+            repetitive.extend_from_slice(b"request_handler::process_batch");
+            repetitive.extend_from_slice(&(i % 4).to_le_bytes());
+            repetitive.extend_from_slice(&[0; 24]);
+        }
+        assert!(round_trip(&repetitive) * 10 < repetitive.len());
+    }
+
+    #[test]
+    fn test_malformed() {
+        let mut buf = Vec::new();
+        // Match offset past the start of the output.
+        let frame = [TAG_LZ, 0x10, b'a', 2, 0];
+        assert_eq!(decode_frame(FrameCompression::Lz, &frame, &mut buf), None);
+        // Literals cut short.
+        assert_eq!(decode_frame(FrameCompression::Lz, &[TAG_LZ, 0x30, b'a'], &mut buf), None);
+        // Unknown tag.
+        assert_eq!(decode_frame(FrameCompression::Lz, &[9], &mut buf), None);
+    }
+}
@@ -0,0 +1,666 @@
+use core::{fmt, str::FromStr, time::Duration};
+use std::path::PathBuf;
+
+use crate::{FrameCompression, Probius, ProbiusFlusher, Sink, VoidSink};
+
+/// bab requires that there are at least `num_threads * 3 / 2` batches to avoid any single thread
+/// getting starved. In Probius's case, there will be at most 2 threads - the publisher and the
+/// flusher (which may or may not run on the same thread).
+///
+/// So we need at least 3 batches, but 4 divides better since people tend to supply large even
+/// numbers for buffer counts.
+pub(crate) const BATCH_COUNT: usize = 4;
+
+/// Every event must fit in a single buffer, and detailed traces are encoded in up to 512 bytes.
+const MIN_BUFFER_SIZE: usize = 1024;
+/// Sinks frame each buffer with a u16 length prefix.
+const MAX_BUFFER_SIZE: usize = u16::MAX as usize;
+/// The app version and resource attributes are sent in the handshake, which is a single frame,
+/// along with the hostname and executable path.
+const MAX_RESOURCE_LEN: usize = 16 * 1024;
+
+/// Configuration for initializing probius, as an alternative to setting up the buffer pool by hand
+/// and calling `init`.
+///
+/// ```no_run
+/// let flusher = probius::ProbiusConfig::from_env()
+///     .expect("invalid probius config")
+///     .app_name("my-service")
+///     .init()
+///     .expect("failed to initialize probius");
+/// flusher.flush();
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProbiusConfig {
+    pub(crate) app_name: String,
+    pub(crate) app_version: String,
+    pub(crate) resource_attributes: Vec<(String, String)>,
+    pub(crate) sink: SinkConfig,
+    pub(crate) compression: FrameCompression,
+    pub(crate) buffer_size: usize,
+    pub(crate) buffer_count: usize,
+    pub(crate) flush_interval: Duration,
+    pub(crate) detailed_trace_interval: u32,
+    pub(crate) drop_policy: DropPolicy,
+    pub(crate) shutdown_timeout: Duration,
+    pub(crate) retry_buffer_count: usize,
+    pub(crate) min_reconnect_backoff: Duration,
+    pub(crate) max_reconnect_backoff: Duration,
+    pub(crate) clock_source: ClockSource,
+    pub(crate) clock_anchor_interval: Duration,
+    pub(crate) heartbeat_interval: Duration,
+}
+
+impl Default for ProbiusConfig {
+    fn default() -> Self {
+        Self {
+            app_name: String::new(),
+            app_version: String::new(),
+            resource_attributes: Vec::new(),
+            sink: SinkConfig::Void,
+            compression: FrameCompression::Uncompressed,
+            buffer_size: 8192,
+            buffer_count: 256,
+            flush_interval: Duration::from_millis(5),
+            detailed_trace_interval: 0,
+            drop_policy: DropPolicy::DropNewest,
+            shutdown_timeout: Duration::from_secs(5),
+            retry_buffer_count: 64,
+            min_reconnect_backoff: Duration::from_millis(100),
+            max_reconnect_backoff: Duration::from_secs(10),
+            clock_source: ClockSource::Monotonic,
+            clock_anchor_interval: Duration::from_secs(60),
+            heartbeat_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ProbiusConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The default configuration overridden by any of the following environment variables:
+    ///
+    /// - `PROBIUS_APP_NAME`
+    /// - `PROBIUS_SINK` - `void`, `tcp://host:port`, `udp://host:port`, `file://directory`,
+    ///   `unix://socket-path` or `unixgram://socket-path`
+    /// - `PROBIUS_COMPRESSION` - `none` or `lz`
+    /// - `PROBIUS_BUFFER_SIZE` - in bytes
+    /// - `PROBIUS_BUFFER_COUNT`
+    /// - `PROBIUS_FLUSH_INTERVAL_MS`
+    /// - `PROBIUS_DETAILED_TRACE_INTERVAL`
+    /// - `PROBIUS_DROP_POLICY` - `drop-newest`, `drop-oldest`, `block:{timeout ms}` or
+    ///   `grow:{max buffer count}`
+    /// - `PROBIUS_SHUTDOWN_TIMEOUT_MS`
+    /// - `PROBIUS_RETRY_BUFFER_COUNT`
+    /// - `PROBIUS_CLOCK` - `monotonic` or `cycle-counter`
+    /// - `PROBIUS_CLOCK_ANCHOR_INTERVAL_MS`
+    /// - `PROBIUS_HEARTBEAT_INTERVAL_MS`
+    /// - `PROBIUS_APP_VERSION`
+    /// - `PROBIUS_RESOURCE_ATTRIBUTES` - `key=value` pairs separated by commas, added to any
+    ///   already configured
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::new().with_env()
+    }
+
+    /// Override this configuration with any of the environment variables listed in `from_env`.
+    pub fn with_env(self) -> Result<Self, ConfigError> {
+        self.with_vars(|name| std::env::var(name).ok())
+    }
+
+    fn with_vars(mut self, var: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        fn parse<T: FromStr>(name: &'static str, value: String) -> Result<T, ConfigError> {
+            value.parse().map_err(|_| ConfigError::InvalidVar { name, value })
+        }
+
+        if let Some(value) = var("PROBIUS_APP_NAME") {
+            self.app_name = value;
+        }
+        if let Some(value) = var("PROBIUS_SINK") {
+            self.sink = parse("PROBIUS_SINK", value)?;
+        }
+        if let Some(value) = var("PROBIUS_COMPRESSION") {
+            let compression = parse_compression(&value);
+            self.compression =
+                compression.ok_or(ConfigError::InvalidVar { name: "PROBIUS_COMPRESSION", value })?;
+        }
+        if let Some(value) = var("PROBIUS_BUFFER_SIZE") {
+            self.buffer_size = parse("PROBIUS_BUFFER_SIZE", value)?;
+        }
+        if let Some(value) = var("PROBIUS_BUFFER_COUNT") {
+            self.buffer_count = parse("PROBIUS_BUFFER_COUNT", value)?;
+        }
+        if let Some(value) = var("PROBIUS_FLUSH_INTERVAL_MS") {
+            let millis = parse("PROBIUS_FLUSH_INTERVAL_MS", value)?;
+            self.flush_interval = Duration::from_millis(millis);
+        }
+        if let Some(value) = var("PROBIUS_DETAILED_TRACE_INTERVAL") {
+            self.detailed_trace_interval = parse("PROBIUS_DETAILED_TRACE_INTERVAL", value)?;
+        }
+        if let Some(value) = var("PROBIUS_DROP_POLICY") {
+            self.drop_policy = parse("PROBIUS_DROP_POLICY", value)?;
+        }
+        if let Some(value) = var("PROBIUS_SHUTDOWN_TIMEOUT_MS") {
+            let millis = parse("PROBIUS_SHUTDOWN_TIMEOUT_MS", value)?;
+            self.shutdown_timeout = Duration::from_millis(millis);
+        }
+        if let Some(value) = var("PROBIUS_RETRY_BUFFER_COUNT") {
+            self.retry_buffer_count = parse("PROBIUS_RETRY_BUFFER_COUNT", value)?;
+        }
+        if let Some(value) = var("PROBIUS_CLOCK") {
+            self.clock_source = parse("PROBIUS_CLOCK", value)?;
+        }
+        if let Some(value) = var("PROBIUS_CLOCK_ANCHOR_INTERVAL_MS") {
+            let millis = parse("PROBIUS_CLOCK_ANCHOR_INTERVAL_MS", value)?;
+            self.clock_anchor_interval = Duration::from_millis(millis);
+        }
+        if let Some(value) = var("PROBIUS_HEARTBEAT_INTERVAL_MS") {
+            let millis = parse("PROBIUS_HEARTBEAT_INTERVAL_MS", value)?;
+            self.heartbeat_interval = Duration::from_millis(millis);
+        }
+        if let Some(value) = var("PROBIUS_APP_VERSION") {
+            self.app_version = value;
+        }
+        if let Some(value) = var("PROBIUS_RESOURCE_ATTRIBUTES") {
+            let Some(attributes) = parse_resource_attributes(&value) else {
+                return Err(ConfigError::InvalidVar { name: "PROBIUS_RESOURCE_ATTRIBUTES", value });
+            };
+            self.resource_attributes.extend(attributes);
+        }
+
+        Ok(self)
+    }
+
+    /// Name the application in the sink handshake.
+    pub fn app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = app_name.into();
+        self
+    }
+
+    /// The version of the application, such as `env!("CARGO_PKG_VERSION")`, sent in the sink
+    /// handshake.
+    pub fn app_version(mut self, app_version: impl Into<String>) -> Self {
+        self.app_version = app_version.into();
+        self
+    }
+
+    /// Add an attribute to the sink handshake, such as the deployment's environment or region,
+    /// so that collectors can group and filter sessions by it.
+    pub fn resource_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.resource_attributes.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn sink(mut self, sink: SinkConfig) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Size in bytes of each buffer, and therefore of each frame sent to the sink.
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Total number of buffers shared by all threads. Rounded up to a multiple of 4.
+    pub fn buffer_count(mut self, buffer_count: usize) -> Self {
+        self.buffer_count = buffer_count;
+        self
+    }
+
+    /// How long the sink thread waits between draining the buffers handed to it by
+    /// `ProbiusFlusher::flush`. The void sink has no thread and releases buffers immediately.
+    pub fn flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Record one in every `interval` traces of each trace source in detail, in addition to its
+    /// aggregate. 0, the default, disables detailed traces.
+    pub fn detailed_trace_interval(mut self, interval: u32) -> Self {
+        self.detailed_trace_interval = interval;
+        self
+    }
+
+    /// How the TCP and file sinks compress buffers. Sinks passed to `init_with_sink` and
+    /// `build_with_sink` are configured on their own.
+    pub fn compression(mut self, compression: FrameCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn drop_policy(mut self, drop_policy: DropPolicy) -> Self {
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    /// How long `shutdown` waits for the sink to send the buffers queued for it.
+    pub fn shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    /// How many buffers that failed to send are kept to be resent once the sink reconnects. When
+    /// more fail, the oldest are dropped. Retried buffers are unavailable to writers, so this is
+    /// capped at half of `buffer_count`.
+    pub fn retry_buffer_count(mut self, retry_buffer_count: usize) -> Self {
+        self.retry_buffer_count = retry_buffer_count;
+        self
+    }
+
+    /// How long the sink thread waits before reconnecting a sink. The wait doubles after each
+    /// failed attempt up to `max`, and goes back to `min` once buffers are sent successfully.
+    pub fn reconnect_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_reconnect_backoff = min;
+        self.max_reconnect_backoff = max.max(min);
+        self
+    }
+
+    /// Where event timestamps are read from.
+    pub fn clock_source(mut self, clock_source: ClockSource) -> Self {
+        self.clock_source = clock_source;
+        self
+    }
+
+    /// How often a `ClockAnchor` event pairs the session's monotonic clock with the wall clock.
+    /// The first is written along with the session's first event.
+    pub fn clock_anchor_interval(mut self, clock_anchor_interval: Duration) -> Self {
+        self.clock_anchor_interval = clock_anchor_interval;
+        self
+    }
+
+    /// How often the sink thread sends a `Heartbeat` event with the session's uptime, buffer
+    /// usage, drop counts and live sources. Zero disables heartbeats.
+    pub fn heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
+        self.heartbeat_interval = heartbeat_interval;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(MIN_BUFFER_SIZE..=MAX_BUFFER_SIZE).contains(&self.buffer_size) {
+            return Err(ConfigError::InvalidBufferSize(self.buffer_size));
+        }
+        // Each thread holds on to up to a whole batch, so a pool with fewer buffers than batches
+        // can't be split between the publisher and the flusher.
+        if self.buffer_count < BATCH_COUNT {
+            return Err(ConfigError::InvalidBufferCount(self.buffer_count));
+        }
+        if self.flush_interval.is_zero() {
+            return Err(ConfigError::ZeroFlushInterval);
+        }
+        if self.app_name.len() > u16::MAX as usize {
+            return Err(ConfigError::AppNameTooLong);
+        }
+        let resource_len = self.app_version.len()
+            + self.resource_attributes.iter()
+                .map(|(key, value)| key.len() + value.len())
+                .sum::<usize>();
+        if resource_len > MAX_RESOURCE_LEN {
+            return Err(ConfigError::ResourceTooLong);
+        }
+        #[cfg(all(feature = "enabled", not(feature = "tcp-sink")))]
+        if let SinkConfig::Tcp { .. } = self.sink {
+            return Err(ConfigError::SinkUnavailable("tcp-sink"));
+        }
+        #[cfg(all(feature = "enabled", not(feature = "udp-sink")))]
+        if let SinkConfig::Udp { .. } = self.sink {
+            return Err(ConfigError::SinkUnavailable("udp-sink"));
+        }
+        #[cfg(all(feature = "enabled", not(feature = "file-sink")))]
+        if let SinkConfig::File { .. } = self.sink {
+            return Err(ConfigError::SinkUnavailable("file-sink"));
+        }
+        #[cfg(all(feature = "enabled", not(all(feature = "unix-sink", unix))))]
+        if let SinkConfig::Unix { .. } | SinkConfig::UnixDatagram { .. } = self.sink {
+            return Err(ConfigError::SinkUnavailable("unix-sink"));
+        }
+
+        Ok(())
+    }
+
+    /// Validate this configuration, set up the global instance's buffer pool and start its sink.
+    ///
+    /// Returns an error instead of panicking if probius was already initialized.
+    pub fn init(self) -> Result<ProbiusFlusher, ConfigError> {
+        self.init_instance(Probius::global())
+    }
+
+    /// Like `init`, but sending buffers to `sink` instead of the configured sink.
+    pub fn init_with_sink(self, sink: impl Sink) -> Result<ProbiusFlusher, ConfigError> {
+        self.init_instance_with_sink(Probius::global(), sink)
+    }
+
+    /// Create a separate `Probius` instance with its own buffer pool and sink.
+    pub fn build(self) -> Result<Probius, ConfigError> {
+        let probius = Probius::new();
+        self.init_instance(&probius)?;
+        Ok(probius)
+    }
+
+    /// Like `build`, but sending buffers to `sink` instead of the configured sink.
+    pub fn build_with_sink(self, sink: impl Sink) -> Result<Probius, ConfigError> {
+        let probius = Probius::new();
+        self.init_instance_with_sink(&probius, sink)?;
+        Ok(probius)
+    }
+
+    /// Like `init`, but for `probius`, which may have been shut down.
+    pub fn init_instance(self, probius: &Probius) -> Result<ProbiusFlusher, ConfigError> {
+        match &self.sink {
+            SinkConfig::Void => self.init_instance_with_sink(probius, VoidSink),
+            #[cfg(feature = "tcp-sink")]
+            SinkConfig::Tcp { addr } => {
+                let sink = crate::TcpSink::new(addr.clone()).compression(self.compression);
+                self.init_instance_with_sink(probius, sink)
+            }
+            #[cfg(feature = "udp-sink")]
+            SinkConfig::Udp { addr } => {
+                let sink = crate::UdpSink::new(addr.clone());
+                self.init_instance_with_sink(probius, sink)
+            }
+            #[cfg(feature = "file-sink")]
+            SinkConfig::File { dir } => {
+                let sink = crate::FileSink::new(dir.clone()).compression(self.compression);
+                self.init_instance_with_sink(probius, sink)
+            }
+            #[cfg(all(feature = "unix-sink", unix))]
+            SinkConfig::Unix { path } => {
+                let sink = crate::UnixSink::new(path.clone());
+                self.init_instance_with_sink(probius, sink)
+            }
+            #[cfg(all(feature = "unix-sink", unix))]
+            SinkConfig::UnixDatagram { path } => {
+                let sink = crate::UnixDatagramSink::new(path.clone());
+                self.init_instance_with_sink(probius, sink)
+            }
+            #[cfg(not(all(
+                feature = "tcp-sink",
+                feature = "udp-sink",
+                feature = "file-sink",
+                feature = "unix-sink",
+                unix,
+            )))]
+            _ => {
+                // Sinks whose feature is off. `init_instance_with_sink` validates the config first,
+                // which rejects them in enabled builds, so only stubbed builds get the void sink.
+                self.init_instance_with_sink(probius, VoidSink)
+            }
+        }
+    }
+
+    #[cfg(feature = "enabled")]
+    pub fn init_instance_with_sink(
+        self,
+        probius: &Probius,
+        sink: impl Sink,
+    ) -> Result<ProbiusFlusher, ConfigError> {
+        self.validate()?;
+        crate::sink::start_sink(probius, &self, Box::new(sink))
+    }
+
+    #[cfg(not(feature = "enabled"))]
+    pub fn init_instance_with_sink(
+        self,
+        _probius: &Probius,
+        _sink: impl Sink,
+    ) -> Result<ProbiusFlusher, ConfigError> {
+        self.validate()?;
+        Ok(ProbiusFlusher::void())
+    }
+
+    #[cfg(feature = "enabled")]
+    pub(crate) fn max_retry_buffers(&self) -> usize {
+        self.retry_buffer_count.min(self.buffer_count / 2)
+    }
+
+    /// Create a buffer pool with at least `buffer_count` buffers of `buffer_size` bytes, of which
+    /// the first `headroom` bytes are reserved for the sink.
+    #[cfg(feature = "enabled")]
+    pub(crate) fn new_buffer_pool(&self, headroom: usize) -> bab::HeapBufferPool {
+        let buffers_per_batch = self.buffer_count.div_ceil(BATCH_COUNT);
+        bab::HeapBufferPool::new(self.buffer_size - headroom, BATCH_COUNT, buffers_per_batch)
+    }
+}
+
+/// Which of the built-in sinks flushed buffers are sent to. Other sinks can be passed to
+/// `ProbiusConfig::init_with_sink` and `ProbiusConfig::build_with_sink`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum SinkConfig {
+    /// Discard all buffers.
+    #[default]
+    Void,
+    /// Stream buffers to a collector over TCP. Requires the `tcp-sink` feature.
+    Tcp { addr: String },
+    /// Send buffers to a collector as UDP datagrams, dropping them rather than ever blocking.
+    /// Requires the `udp-sink` feature.
+    Udp { addr: String },
+    /// Write buffers to files in `dir`. Requires the `file-sink` feature. Use `FileSink` directly
+    /// for rotation and retention.
+    File { dir: PathBuf },
+    /// Stream buffers to a local collector over a Unix socket. Requires the `unix-sink` feature.
+    Unix { path: PathBuf },
+    /// Send buffers to a local collector as Unix datagrams, dropping them when the collector
+    /// falls behind. Requires the `unix-sink` feature.
+    UnixDatagram { path: PathBuf },
+}
+
+impl FromStr for SinkConfig {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "void" {
+            return Ok(SinkConfig::Void);
+        }
+        if let Some(addr) = s.strip_prefix("tcp://") && !addr.is_empty() {
+            return Ok(SinkConfig::Tcp { addr: addr.to_string() });
+        }
+        if let Some(addr) = s.strip_prefix("udp://") && !addr.is_empty() {
+            return Ok(SinkConfig::Udp { addr: addr.to_string() });
+        }
+        if let Some(dir) = s.strip_prefix("file://") && !dir.is_empty() {
+            return Ok(SinkConfig::File { dir: dir.into() });
+        }
+        if let Some(path) = s.strip_prefix("unix://") && !path.is_empty() {
+            return Ok(SinkConfig::Unix { path: path.into() });
+        }
+        if let Some(path) = s.strip_prefix("unixgram://") && !path.is_empty() {
+            return Ok(SinkConfig::UnixDatagram { path: path.into() });
+        }
+        Err(())
+    }
+}
+
+fn parse_resource_attributes(s: &str) -> Option<Vec<(String, String)>> {
+    s.split(',')
+        .filter(|attribute| !attribute.trim().is_empty())
+        .map(|attribute| {
+            let (key, value) = attribute.split_once('=')?;
+            let key = key.trim();
+            (!key.is_empty()).then(|| (key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn parse_compression(s: &str) -> Option<FrameCompression> {
+    match s {
+        "none" => Some(FrameCompression::Uncompressed),
+        "lz" => Some(FrameCompression::Lz),
+        _ => None,
+    }
+}
+
+/// Where a session's clock reads event timestamps from. Whichever it is, timestamps are nanoseconds
+/// since the session started.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ClockSource {
+    /// std's monotonic clock, `Instant`.
+    #[default]
+    Monotonic,
+    /// The CPU's cycle counter, which is cheaper to read than `Instant` on some machines, timed
+    /// against `Instant` when the session starts and every second afterwards. Only available on
+    /// aarch64 Linux, and on x86_64 Linux with an invariant TSC. Falls back to `Monotonic`
+    /// elsewhere, which `Probius::clock_source` tells.
+    CycleCounter,
+}
+
+impl FromStr for ClockSource {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "monotonic" => Ok(ClockSource::Monotonic),
+            "cycle-counter" => Ok(ClockSource::CycleCounter),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What to do with an event when the buffer pool has run out of free buffers. What each policy
+/// did is counted in `Probius::backpressure_stats`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DropPolicy {
+    /// Discard the event being written.
+    #[default]
+    DropNewest,
+    /// Discard the writing thread's oldest buffer that hasn't been flushed to the sink yet, and
+    /// reuse it for the event being written. Falls back to `DropNewest` if the thread has no such
+    /// buffer.
+    DropOldest,
+    /// Flush the writing thread's buffers to the sink and wait up to `timeout` for a buffer to be
+    /// freed, then fall back to `DropNewest`.
+    Block { timeout: Duration },
+    /// Add buffers to the pool, `buffer_count` at a time, up to `max_buffer_count` buffers in
+    /// total, then fall back to `DropNewest`. Added buffers are kept until the instance is shut
+    /// down.
+    Grow { max_buffer_count: usize },
+}
+
+impl FromStr for DropPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(timeout_ms) = s.strip_prefix("block:") {
+            let timeout = Duration::from_millis(timeout_ms.parse().map_err(|_| ())?);
+            return Ok(DropPolicy::Block { timeout });
+        }
+        if let Some(max_buffer_count) = s.strip_prefix("grow:") {
+            let max_buffer_count = max_buffer_count.parse().map_err(|_| ())?;
+            return Ok(DropPolicy::Grow { max_buffer_count });
+        }
+        match s {
+            "drop-newest" => Ok(DropPolicy::DropNewest),
+            "drop-oldest" => Ok(DropPolicy::DropOldest),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConfigError {
+    InvalidBufferSize(usize),
+    InvalidBufferCount(usize),
+    ZeroFlushInterval,
+    AppNameTooLong,
+    /// The app version and resource attributes add up to too many bytes.
+    ResourceTooLong,
+    /// The sink requires a cargo feature that isn't enabled.
+    SinkUnavailable(&'static str),
+    InvalidVar { name: &'static str, value: String },
+    AlreadyInitialized,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidBufferSize(size) => write!(
+                f,
+                "buffer size {size} is outside of {MIN_BUFFER_SIZE}..={MAX_BUFFER_SIZE}",
+            ),
+            ConfigError::InvalidBufferCount(count) => {
+                write!(f, "buffer count {count} is less than {BATCH_COUNT}")
+            }
+            ConfigError::ZeroFlushInterval => write!(f, "flush interval is zero"),
+            ConfigError::AppNameTooLong => write!(f, "app name is too long"),
+            ConfigError::ResourceTooLong => {
+                write!(f, "app version and resource attributes are over {MAX_RESOURCE_LEN} bytes")
+            }
+            ConfigError::SinkUnavailable(feature) => {
+                write!(f, "sink requires the probius `{feature}` feature")
+            }
+            ConfigError::InvalidVar { name, value } => write!(f, "invalid {name}={value:?}"),
+            ConfigError::AlreadyInitialized => write!(f, "probius is already initialized"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError { }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_validation() {
+        assert_eq!(ProbiusConfig::new().validate(), Ok(()));
+        assert_eq!(
+            ProbiusConfig::new().buffer_size(100).validate(),
+            Err(ConfigError::InvalidBufferSize(100)),
+        );
+        assert_eq!(
+            ProbiusConfig::new().buffer_count(3).validate(),
+            Err(ConfigError::InvalidBufferCount(3)),
+        );
+        assert_eq!(
+            ProbiusConfig::new().flush_interval(Duration::ZERO).validate(),
+            Err(ConfigError::ZeroFlushInterval),
+        );
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let vars = [
+            ("PROBIUS_APP_NAME", "test-app"),
+            ("PROBIUS_RESOURCE_ATTRIBUTES", "env=prod, region = eu-west-1"),
+            ("PROBIUS_SINK", "tcp://localhost:4000"),
+            ("PROBIUS_BUFFER_COUNT", "64"),
+            ("PROBIUS_FLUSH_INTERVAL_MS", "100"),
+            ("PROBIUS_DROP_POLICY", "block:20"),
+            ("PROBIUS_COMPRESSION", "lz"),
+            ("PROBIUS_CLOCK", "cycle-counter"),
+            ("PROBIUS_CLOCK_ANCHOR_INTERVAL_MS", "10000"),
+            ("PROBIUS_HEARTBEAT_INTERVAL_MS", "0"),
+        ];
+        let var = |name: &str| {
+            vars.iter().find(|(n, _)| *n == name).map(|(_, value)| value.to_string())
+        };
+        let config = ProbiusConfig::new().with_vars(var).unwrap();
+        assert_eq!(
+            config,
+            ProbiusConfig::new()
+                .app_name("test-app")
+                .resource_attribute("env", "prod")
+                .resource_attribute("region", "eu-west-1")
+                .sink(SinkConfig::Tcp { addr: "localhost:4000".into() })
+                .buffer_count(64)
+                .flush_interval(Duration::from_millis(100))
+                .drop_policy(DropPolicy::Block { timeout: Duration::from_millis(20) })
+                .compression(FrameCompression::Lz)
+                .clock_source(ClockSource::CycleCounter)
+                .clock_anchor_interval(Duration::from_secs(10))
+                .heartbeat_interval(Duration::ZERO),
+        );
+
+        let config = ProbiusConfig::new().with_vars(|_| Some("bogus".into()));
+        assert_eq!(
+            config,
+            Err(ConfigError::InvalidVar { name: "PROBIUS_SINK", value: "bogus".into() }),
+        );
+
+        assert_eq!(
+            "file:///var/log/probius".parse(),
+            Ok(SinkConfig::File { dir: "/var/log/probius".into() }),
+        );
+    }
+}
@@ -1,5 +1,14 @@
 use mproto::BaseLen;
 
+use crate::{
+    FrameCompression, PROTOCOL_VERSION, ProtocolFeatures, Resource, SinkSession, SourceId,
+    compression,
+};
+
+/// The source of events about the session as a whole rather than any one of its sources, such as
+/// `ClockAnchor` and `Heartbeat` events. No source is ever given this id.
+pub const SESSION_SOURCE: SourceId = SourceId { source: u64::MAX };
+
 pub struct DecodeEvents<'a> {
     buf: &'a [u8],
     offset: usize,
@@ -67,6 +76,26 @@ impl<'a> Iterator for DecodeEvents<'a> {
                     body: DecodeEventBody::TraceAggregate { header },
                 })
             }
+            probius_mproto::EventKind::ClockAnchor => {
+                let anchor = mproto::decode_value(&self.buf[body_start..]).ok()?;
+                Some(DecodeEvent {
+                    buffer_offset,
+                    buffer_body_len,
+                    kind: event.kind,
+                    id: event.id,
+                    body: DecodeEventBody::ClockAnchor(anchor),
+                })
+            }
+            probius_mproto::EventKind::Heartbeat => {
+                let heartbeat = mproto::decode_value(&self.buf[body_start..]).ok()?;
+                Some(DecodeEvent {
+                    buffer_offset,
+                    buffer_body_len,
+                    kind: event.kind,
+                    id: event.id,
+                    body: DecodeEventBody::Heartbeat(heartbeat),
+                })
+            }
             _ => todo!(),
         }
     }
@@ -94,5 +123,154 @@ pub enum DecodeEventBody<'a> {
         header: probius_mproto::TraceAggregateLazy<'a>,
     },
     TraceAggregateDelta(probius_mproto::TraceAggregateLazy<'a>),
+    /// Pairs the session's monotonic clock, which event timestamps are measured on, with the wall
+    /// clock. Sent by `SESSION_SOURCE` when the session starts and periodically afterwards.
+    ClockAnchor(probius_mproto::ClockAnchor),
+    /// Sent by `SESSION_SOURCE` from the sink's thread every `ProbiusConfig::heartbeat_interval`,
+    /// whether or not anything else happened, so that a gap in heartbeats means the session
+    /// stalled or lost its sink.
+    Heartbeat(probius_mproto::Heartbeat),
 }
 
+
+/// Splits the byte stream written by the TCP and file sinks into frames, each prefixed with its
+/// u16 length. The first frame of a stream is its `SinkHandshake` (see `DecodeHandshake`), and the
+/// rest can be passed to `DecodeEvents` once decompressed with `DecodeHandshake::decode_frame`.
+pub struct DecodeFrames<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> DecodeFrames<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    /// The bytes left over after the last complete frame, such as a frame that was being written
+    /// when the stream was cut off.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.buf
+    }
+}
+
+impl<'a> Iterator for DecodeFrames<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (len, rest) = self.buf.split_first_chunk::<2>()?;
+        let frame = rest.get(..u16::from_le_bytes(*len) as usize)?;
+        self.buf = &rest[frame.len()..];
+        Some(frame)
+    }
+}
+
+/// The first frame of a stream written by a sink.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecodeHandshake {
+    pub session: SinkSession,
+    /// Whether the frames that follow were spooled while the collector was unreachable, and so
+    /// are older than those on the session's live connection.
+    pub is_replay: bool,
+    /// How the frames that follow are compressed.
+    pub compression: FrameCompression,
+    /// The features used by the frames that follow.
+    pub features: ProtocolFeatures,
+    /// Whether the sink waits for a reply (see `reply`) before sending any frames.
+    pub expects_reply: bool,
+}
+
+impl DecodeHandshake {
+    /// Decode a handshake, or return `None` if it's malformed or from a sink speaking another
+    /// version of the protocol (see `protocol_version`).
+    pub fn new(frame: &[u8]) -> Option<Self> {
+        if Self::protocol_version(frame)? != PROTOCOL_VERSION {
+            return None;
+        }
+        let handshake: probius_mproto::SinkHandshake = mproto::decode_value(frame).ok()?;
+        let session_id =
+            (handshake.session_id_hi as u128) << 64 | handshake.session_id_lo as u128;
+        let resource = Resource {
+            hostname: handshake.hostname,
+            pid: handshake.pid,
+            executable: handshake.executable,
+            app_version: handshake.app_version,
+            probius_version: handshake.probius_version,
+            start_time_unix_nanos: handshake.start_time_unix_nanos,
+            attributes: handshake.attributes.into_iter()
+                .map(|attribute| (attribute.key, attribute.value))
+                .collect(),
+        };
+        Some(Self {
+            session: SinkSession { app_name: handshake.app_name, session_id, resource },
+            is_replay: handshake.is_replay,
+            compression: handshake.compression,
+            features: ProtocolFeatures::from_bits(handshake.features),
+            expects_reply: handshake.expects_reply,
+        })
+    }
+
+    /// The protocol version of a handshake. From protocol version 1 on it comes first, so it can be
+    /// read even when the rest of the handshake can't. Older handshakes started with `app_name`
+    /// instead, and yield a meaningless version here.
+    pub fn protocol_version(frame: &[u8]) -> Option<u16> {
+        mproto::decode_value(frame).ok()
+    }
+
+    /// The length-prefixed reply to send back to a sink that `expects_reply`, accepting the
+    /// session if the collector `supported` all of its features.
+    pub fn reply(&self, supported: ProtocolFeatures) -> Vec<u8> {
+        DecodeHandshakeReply {
+            protocol_version: PROTOCOL_VERSION,
+            features: self.features.intersection(supported),
+        }
+        .encode()
+    }
+
+    /// The events in `frame`, one of the frames that follow this handshake, ready to be passed to
+    /// `DecodeEvents`. Compressed frames are decompressed into `buf`. Returns `None` if the frame
+    /// is malformed.
+    pub fn decode_frame<'a>(&self, frame: &'a [u8], buf: &'a mut Vec<u8>) -> Option<&'a [u8]> {
+        compression::decode_frame(self.compression, frame, buf)
+    }
+}
+
+/// A collector's reply to a handshake, saying which protocol version it speaks and which of the
+/// sink's features it accepts. Sinks that wait for a reply disconnect if the version differs from
+/// theirs or any of their features weren't accepted.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DecodeHandshakeReply {
+    pub protocol_version: u16,
+    pub features: ProtocolFeatures,
+}
+
+impl DecodeHandshakeReply {
+    pub fn new(frame: &[u8]) -> Option<Self> {
+        let reply: probius_mproto::SinkHandshakeReply = mproto::decode_value(frame).ok()?;
+        Some(Self {
+            protocol_version: reply.protocol_version,
+            features: ProtocolFeatures::from_bits(reply.features),
+        })
+    }
+
+    /// This reply as a length-prefixed frame. Collectors that can't decode a handshake should
+    /// still reply with their own `PROTOCOL_VERSION`, so that the sink can report the mismatch.
+    pub fn encode(&self) -> Vec<u8> {
+        let reply = probius_mproto::SinkHandshakeReply {
+            protocol_version: self.protocol_version,
+            features: self.features.bits(),
+        };
+        let reply_len = mproto::encoded_len(reply);
+        let mut reply_buf = vec![0u8; 2 + reply_len];
+        reply_buf[..2].copy_from_slice(&(reply_len as u16).to_le_bytes());
+        mproto::encode_value(reply, &mut reply_buf[2..]);
+        reply_buf
+    }
+}
+
+/// Split a datagram sent by the UDP sink into its header and payload. Sequence number 0 carries
+/// the `SinkHandshake`, and the rest carry buffers that can be passed to `DecodeEvents` once all
+/// `fragment_count` fragments with the same sequence number have been joined in order.
+pub fn decode_udp_datagram(datagram: &[u8]) -> Option<(probius_mproto::UdpDatagramHeader, &[u8])> {
+    let header = mproto::decode_value(datagram).ok()?;
+    let payload = datagram.get(probius_mproto::UdpDatagramHeader::BASE_LEN..)?;
+    Some((header, payload))
+}
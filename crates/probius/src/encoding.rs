@@ -1,37 +1,68 @@
 use core::cell::RefCell;
+use std::{
+    sync::{Arc, Mutex, MutexGuard, Weak},
+    time::Instant,
+};
 
-use crate::SourceId;
+use crate::{
+    DropPolicy, ProbiusFlusher, SESSION_SOURCE, SourceId,
+    backpressure::{Backpressure, block_on_timeout},
+};
 
 pub struct ProbiusWriter {
     buffer_headroom: usize,
+    buffer_size: usize,
+    backpressure: Arc<Backpressure>,
+    // Used by `DropPolicy::Block` to free up buffers before waiting.
+    flusher: Option<ProbiusFlusher>,
     buffer_writer: RefCell<bab::BufferWriter>,
-    written_buffers: bab::BufferChain,
+    // Shared with `Probius::shutdown`, which takes them from other threads' writers.
+    written_buffers: Arc<Mutex<bab::BufferChain>>,
 }
 
 impl ProbiusWriter {
-    pub fn new(buffer_headroom: usize, buffer_pool: bab::HeapBufferPool) -> Self {
+    pub fn new(
+        buffer_headroom: usize,
+        backpressure: Arc<Backpressure>,
+        flusher: Option<ProbiusFlusher>,
+    ) -> Self {
+        let buffer_pool = backpressure.buffer_pool();
         Self {
             buffer_headroom,
+            buffer_size: buffer_pool.buffer_size(),
+            backpressure,
+            flusher,
             buffer_writer: RefCell::new(bab::BufferWriter::new(buffer_pool)),
-            written_buffers: bab::BufferChain::new(),
+            written_buffers: Arc::new(Mutex::new(bab::BufferChain::new())),
         }
     }
 
     pub fn flush(&self) -> impl Iterator<Item = bab::BufferPtr> + use<> {
         self.switch_buffer();
-        self.written_buffers.drain()
+        self.lock_written_buffers().drain()
     }
 
-    fn try_write<R>(&self, len: usize, f: impl FnOnce(&mut [u8]) -> R) -> Option<R> {
-        let mut headroom = 0;
-        if self.buffer_writer.borrow().is_empty() {
-            headroom = self.buffer_headroom;
-        }
+    /// The buffers that this writer has completed but not yet flushed, which can be taken from
+    /// any thread. The buffer it is still writing to isn't among them.
+    pub fn written_buffers(&self) -> Weak<Mutex<bab::BufferChain>> {
+        Arc::downgrade(&self.written_buffers)
+    }
 
-        if headroom + len > self.buffer_writer.borrow().remaining_on_buffer() {
-            self.switch_buffer()?;
-            headroom = self.buffer_headroom;
+    fn lock_written_buffers(&self) -> MutexGuard<'_, bab::BufferChain> {
+        self.written_buffers.lock().expect("probius written buffers lock")
+    }
+
+    fn try_write<R>(&self, len: usize, f: impl FnOnce(&mut [u8]) -> R) -> Option<R> {
+        if self.buffer_headroom + len > self.buffer_size {
+            // Too large for any buffer.
+            self.backpressure.record_dropped_event();
+            return None;
         }
+        let Some(headroom) = self.try_reserve(len).or_else(|| self.reserve_under_pressure(len))
+        else {
+            self.backpressure.record_dropped_event();
+            return None;
+        };
 
         let mut buffer_writer = self.buffer_writer.borrow_mut();
         let write_buf = buffer_writer.try_write()?.get_mut(headroom..headroom + len)?;
@@ -50,11 +81,73 @@ impl ProbiusWriter {
         Some(result)
     }
 
+    /// Make sure that the current buffer has room for `len` bytes, returning the offset to write
+    /// them at. Fails if the pool has no free buffers.
+    fn try_reserve(&self, len: usize) -> Option<usize> {
+        let mut headroom = 0;
+        if self.buffer_writer.borrow().is_empty() {
+            headroom = self.buffer_headroom;
+        }
+
+        if headroom + len > self.buffer_writer.borrow().remaining_on_buffer() {
+            self.switch_buffer()?;
+            headroom = self.buffer_headroom;
+        }
+
+        self.buffer_writer.borrow_mut().try_write()?;
+        Some(headroom)
+    }
+
+    /// Apply the drop policy after `try_reserve` found the pool empty.
+    fn reserve_under_pressure(&self, len: usize) -> Option<usize> {
+        match self.backpressure.policy {
+            DropPolicy::DropNewest => None,
+            DropPolicy::DropOldest => {
+                let written_buffers = self.lock_written_buffers();
+                let mut buffers = written_buffers.drain();
+                let oldest = buffers.next()?;
+                for buffer in buffers {
+                    written_buffers.push(buffer);
+                }
+                drop(written_buffers);
+                // The buffer goes back to this thread's stock in the pool, ready to be reused.
+                unsafe { oldest.release(); }
+                self.backpressure.record_dropped_buffer();
+                self.try_reserve(len)
+            }
+            DropPolicy::Block { timeout } => {
+                if let Some(flusher) = &self.flusher {
+                    flusher.send(self.lock_written_buffers().drain());
+                }
+                let start = Instant::now();
+                let acquired =
+                    block_on_timeout(self.buffer_writer.borrow_mut().write(), timeout).is_some();
+                self.backpressure.record_blocked_write(start.elapsed());
+                if !acquired {
+                    return None;
+                }
+                self.try_reserve(len)
+            }
+            DropPolicy::Grow { max_buffer_count } => {
+                // Other pools may have free buffers again, and otherwise a new one is added.
+                for pool in self.backpressure.pools() {
+                    *self.buffer_writer.borrow_mut() = bab::BufferWriter::new(pool);
+                    if let Some(headroom) = self.try_reserve(len) {
+                        return Some(headroom);
+                    }
+                }
+                let pool = self.backpressure.grow(max_buffer_count)?;
+                *self.buffer_writer.borrow_mut() = bab::BufferWriter::new(pool);
+                self.try_reserve(len)
+            }
+        }
+    }
+
     fn switch_buffer(&self) -> Option<()> {
         let mut buffer_writer = self.buffer_writer.borrow_mut();
         let (buffer, written_len) = buffer_writer.next_buffer()?;
         bab::WriterFlushSender::mark_complete_buffer(buffer, written_len as u32);
-        self.written_buffers.push(buffer);
+        self.lock_written_buffers().push(buffer);
         Some(())
     }
 
@@ -66,22 +159,14 @@ impl ProbiusWriter {
         payload: impl mproto::Encode,
     ) {
         let payload_len = mproto::encoded_len(&payload);
-        let header = probius_mproto::EventHeader {
-            id: event_id,
-            len: payload_len as u16,
-            kind,
-        };
+        let header = event_header(event_id, kind, payload_len);
         let header_len = mproto::encoded_len(header);
 
-        if
-            self.try_write(header_len + payload_len, |buf| {
-                mproto::encode_value(header, &mut buf[..header_len]);
-                mproto::encode_value(payload, &mut buf[header_len..]);
-            })
-            .is_none()
-        {
-            // TODO record that we dropped tracing data due to insufficient buffer space.
-        };
+        // Dropped events are counted by `try_write`.
+        self.try_write(header_len + payload_len, |buf| {
+            mproto::encode_value(header, &mut buf[..header_len]);
+            mproto::encode_value(payload, &mut buf[header_len..]);
+        });
     }
 
     pub fn create_source(
@@ -140,10 +225,52 @@ impl ProbiusWriter {
             probius_mproto::TraceGen { start_nanos, trace },
         );
     }
+
+    pub fn clock_anchor(&self, anchor: probius_mproto::ClockAnchor) {
+        let event_id = probius_mproto::EventId {
+            source: SESSION_SOURCE,
+            timestamp_nanos: anchor.monotonic_nanos,
+            seq: probius_mproto::EventSeq { seq: 0 },
+        };
+        self.write_event(event_id, probius_mproto::EventKind::ClockAnchor, anchor);
+    }
+}
+
+#[inline]
+fn event_header(
+    event_id: probius_mproto::EventId,
+    kind: probius_mproto::EventKind,
+    payload_len: usize,
+) -> probius_mproto::EventHeader {
+    probius_mproto::EventHeader {
+        id: event_id,
+        len: payload_len as u16,
+        kind,
+    }
+}
+
+/// Append an event to `buf` as a `ProbiusWriter` would write it, for events that are sent without
+/// going through the buffer pool.
+pub fn encode_event(
+    buf: &mut Vec<u8>,
+    event_id: probius_mproto::EventId,
+    kind: probius_mproto::EventKind,
+    payload: impl mproto::Encode,
+) {
+    let payload_len = mproto::encoded_len(&payload);
+    let header = event_header(event_id, kind, payload_len);
+    let header_len = mproto::encoded_len(header);
+
+    let start = buf.len();
+    buf.resize(start + header_len + payload_len, 0);
+    mproto::encode_value(header, &mut buf[start..start + header_len]);
+    mproto::encode_value(payload, &mut buf[start + header_len..]);
 }
 
 #[cfg(test)]
 mod test {
+    use std::time::Duration;
+
     use super::*;
     use crate::decode::{DecodeEvents, DecodeEventBody};
 
@@ -151,7 +278,8 @@ mod test {
     fn test_encoding() {
         let buffer_pool = bab::HeapBufferPool::new(8192, 4, 16);
         let headroom = 10;
-        let writer = ProbiusWriter::new(headroom, buffer_pool);
+        let backpressure = Arc::new(Backpressure::new(DropPolicy::DropNewest, buffer_pool));
+        let writer = ProbiusWriter::new(headroom, backpressure, None);
 
         writer.create_source(
             probius_mproto::EventId {
@@ -189,4 +317,55 @@ mod test {
             }
         }
     }
+
+    fn write_events(policy: DropPolicy) -> (Arc<Backpressure>, Vec<u64>) {
+        // Four 64 byte buffers, each of which fits one event.
+        let buffer_pool = bab::HeapBufferPool::new(64, 4, 1);
+        let backpressure = Arc::new(Backpressure::new(policy, buffer_pool));
+        let writer = ProbiusWriter::new(0, backpressure.clone(), None);
+        for i in 0..8 {
+            let event_id = probius_mproto::EventId {
+                source: SourceId { source: i },
+                timestamp_nanos: 0,
+                seq: probius_mproto::EventSeq { seq: 0 },
+            };
+            writer.create_source(event_id, "source", None, true);
+        }
+
+        let mut sources = Vec::new();
+        for buffer in writer.flush() {
+            let len = bab::WriterFlushSender::get_complete_buffer_len(buffer) as usize;
+            let events = DecodeEvents::new(unsafe { buffer.slice(0..len) });
+            sources.extend(events.map(|event| event.id.source.source));
+            unsafe { buffer.release(); }
+        }
+        (backpressure, sources)
+    }
+
+    #[test]
+    fn test_drop_policies() {
+        let (backpressure, sources) = write_events(DropPolicy::DropNewest);
+        assert_eq!(sources, [0, 1, 2, 3]);
+        assert_eq!(backpressure.stats().dropped_events, 4);
+
+        let (backpressure, sources) = write_events(DropPolicy::DropOldest);
+        assert_eq!(sources, [4, 5, 6, 7]);
+        assert_eq!(backpressure.stats().dropped_buffers, 4);
+        assert_eq!(backpressure.stats().dropped_events, 0);
+
+        let (backpressure, sources) = write_events(DropPolicy::Grow { max_buffer_count: 6 });
+        assert_eq!(sources, [0, 1, 2, 3]);
+        assert_eq!(backpressure.stats().grown_buffers, 0);
+        let (backpressure, sources) = write_events(DropPolicy::Grow { max_buffer_count: 8 });
+        assert_eq!(sources, [0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(backpressure.stats().grown_buffers, 4);
+        assert_eq!(backpressure.stats().dropped_events, 0);
+
+        let timeout = Duration::from_millis(1);
+        let (backpressure, sources) = write_events(DropPolicy::Block { timeout });
+        assert_eq!(sources, [0, 1, 2, 3]);
+        let stats = backpressure.stats();
+        assert_eq!((stats.blocked_writes, stats.dropped_events), (4, 4));
+        assert!(stats.blocked_nanos >= 4 * timeout.as_nanos() as u64);
+    }
 }
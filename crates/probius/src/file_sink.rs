@@ -0,0 +1,366 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{FrameCompression, HandshakeOptions, Sink, SinkSession, compression::FrameEncoder};
+
+const FILE_EXTENSION: &str = "probius";
+
+/// Writes buffers to append-only files in a directory, in the same framing as `TcpSink`: a
+/// length-prefixed `SinkHandshake` followed by each buffer prefixed with its u16 length.
+///
+/// Every file starts with its own handshake, so each can be read on its own with `DecodeFrames`.
+/// Files are named `{prefix}-{unix millis}-{index}.probius`, which sorts oldest first.
+pub struct FileSink {
+    dir: PathBuf,
+    prefix: Option<String>,
+    max_file_size: Option<u64>,
+    max_file_age: Option<Duration>,
+    max_files: Option<usize>,
+    fsync: FsyncPolicy,
+    frame_encoder: FrameEncoder,
+
+    session: Option<SinkSession>,
+    file: Option<OpenFile>,
+    file_index: u64,
+}
+
+/// When `FileSink` asks the OS to write its files through to disk.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum FsyncPolicy {
+    /// Leave it to the OS.
+    Never,
+    /// When a file is rotated out or the sink shuts down.
+    #[default]
+    OnRotate,
+    /// After every batch of buffers, as well as on rotation.
+    EveryBatch,
+}
+
+struct OpenFile {
+    writer: BufWriter<File>,
+    len: u64,
+    handshake_len: u64,
+    opened_at: Instant,
+}
+
+impl FileSink {
+    /// Write files to `dir`, which is created if it doesn't exist. By default files are never
+    /// rotated or deleted.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: None,
+            max_file_size: None,
+            max_file_age: None,
+            max_files: None,
+            fsync: FsyncPolicy::default(),
+            frame_encoder: FrameEncoder::new(FrameCompression::Uncompressed),
+            session: None,
+            file: None,
+            file_index: 0,
+        }
+    }
+
+    /// Start file names with `prefix` instead of the app name.
+    pub fn file_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Start a new file once the current one would grow past `max_file_size` bytes. A single
+    /// buffer is never split between files.
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    /// Start a new file once the current one has been open for `max_file_age`.
+    pub fn max_file_age(mut self, max_file_age: Duration) -> Self {
+        self.max_file_age = Some(max_file_age);
+        self
+    }
+
+    /// Delete the oldest files with this sink's prefix so that at most `max_files` are kept,
+    /// including the one being written.
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = Some(max_files.max(1));
+        self
+    }
+
+    pub fn fsync(mut self, fsync: FsyncPolicy) -> Self {
+        self.fsync = fsync;
+        self
+    }
+
+    /// Compress each buffer's payload, as announced in each file's handshake. Defaults to
+    /// `FrameCompression::Uncompressed`.
+    pub fn compression(mut self, compression: FrameCompression) -> Self {
+        self.frame_encoder = FrameEncoder::new(compression);
+        self
+    }
+
+    fn file_prefix_for(&self, session: &SinkSession) -> String {
+        match &self.prefix {
+            Some(prefix) => prefix.clone(),
+            None if session.app_name.is_empty() => "probius".to_string(),
+            None => session.app_name.clone(),
+        }
+    }
+
+    /// Close the current file, if any, and start a new one with the session's handshake.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.close_file()?;
+
+        let Some(session) = &self.session else {
+            return Err(io::ErrorKind::NotConnected.into());
+        };
+        let prefix = self.file_prefix_for(session);
+        let handshake = session.handshake(&HandshakeOptions {
+            compression: self.frame_encoder.compression(),
+            ..HandshakeOptions::default()
+        });
+
+        fs::create_dir_all(&self.dir)?;
+        let created_millis = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let file_name =
+            format!("{prefix}-{created_millis:013}-{:06}.{FILE_EXTENSION}", self.file_index);
+        self.file_index += 1;
+
+        let file = File::options().create_new(true).append(true).open(self.dir.join(file_name))?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(&(handshake.len() as u16).to_le_bytes())?;
+        writer.write_all(&handshake)?;
+        let handshake_len = 2 + handshake.len() as u64;
+        self.file = Some(OpenFile {
+            writer,
+            len: handshake_len,
+            handshake_len,
+            opened_at: Instant::now(),
+        });
+
+        if let Some(max_files) = self.max_files {
+            remove_old_files(&self.dir, &prefix, max_files)?;
+        }
+        Ok(())
+    }
+
+    fn close_file(&mut self) -> io::Result<()> {
+        let Some(mut file) = self.file.take() else {
+            return Ok(());
+        };
+        file.writer.flush()?;
+        if self.fsync != FsyncPolicy::Never {
+            file.writer.get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn should_rotate(&self, frame_len: usize) -> bool {
+        let Some(file) = &self.file else {
+            return true;
+        };
+        if file.len == file.handshake_len {
+            // Oversized frames still get a file of their own.
+            return false;
+        }
+        let is_full = self.max_file_size.is_some_and(|max| file.len + frame_len as u64 > max);
+        let is_old = self.max_file_age.is_some_and(|max| file.opened_at.elapsed() >= max);
+        is_full || is_old
+    }
+}
+
+impl Sink for FileSink {
+    fn headroom(&self) -> usize {
+        2 // headroom for u16 length prefix
+    }
+
+    fn connect(&mut self, session: &SinkSession) -> io::Result<()> {
+        // Any file left open by a failed write may end in a partial frame, so start afresh.
+        self.file = None;
+        self.session = Some(session.clone());
+        self.rotate()
+    }
+
+    fn send(&mut self, frame: &mut [u8]) -> io::Result<()> {
+        // Rotate before compressing, going by how long the frame could get.
+        if self.should_rotate(self.frame_encoder.max_encoded_len(frame.len())) {
+            self.rotate()?;
+        }
+        let Some(file) = &mut self.file else {
+            return Err(io::ErrorKind::NotConnected.into());
+        };
+
+        let frame = self.frame_encoder.encode(frame);
+        if let Err(e) = file.writer.write_all(frame) {
+            self.file = None;
+            return Err(e);
+        }
+        file.len += frame.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+        file.writer.flush()?;
+        if self.fsync == FsyncPolicy::EveryBatch {
+            file.writer.get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        let _ = self.close_file();
+    }
+}
+
+/// Delete all but the newest `max_files` files written by a `FileSink` with `prefix` in `dir`.
+fn remove_old_files(dir: &Path, prefix: &str, max_files: usize) -> io::Result<()> {
+    let mut file_names: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|file_name| is_sink_file(file_name, prefix))
+        .collect();
+    if file_names.len() <= max_files {
+        return Ok(());
+    }
+
+    file_names.sort();
+    for file_name in &file_names[..file_names.len() - max_files] {
+        fs::remove_file(dir.join(file_name))?;
+    }
+    Ok(())
+}
+
+/// Whether `file_name` has the form `{prefix}-{unix millis}-{index}.probius`.
+fn is_sink_file(file_name: &str, prefix: &str) -> bool {
+    let Some(rest) = file_name
+        .strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .and_then(|rest| rest.strip_suffix(FILE_EXTENSION))
+        .and_then(|rest| rest.strip_suffix('.'))
+    else {
+        return false;
+    };
+    let mut parts = rest.split('-');
+    let is_number = |part: Option<&str>| {
+        part.is_some_and(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    };
+    is_number(parts.next()) && is_number(parts.next()) && parts.next().is_none()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        DecodeEventBody, DecodeEvents, DecodeFrames, DecodeHandshake, ProbiusConfig,
+        sink::test::watch::SinkWatch,
+    };
+
+    #[test]
+    fn test_file_rotation_and_retention() {
+        let dir = std::env::temp_dir().join(format!("probius-file-sink-{}", fastrand::u64(..)));
+        let sink = FileSink::new(&dir)
+            .max_file_size(200)
+            .max_files(3)
+            .fsync(FsyncPolicy::EveryBatch);
+        let watch = SinkWatch::default();
+        let probius = ProbiusConfig::new()
+            .app_name("file-test")
+            .buffer_size(1024)
+            .build_with_sink(watch.watch(sink))
+            .unwrap();
+
+        for i in 0..10 {
+            drop(probius.new_component(&format!("component-{i}")));
+            probius.flush();
+            // Wait for the sink thread so that each flush gets its own frame.
+            watch.wait_for(|calls| calls.sends > i);
+        }
+        probius.shutdown();
+
+        let mut file_names: Vec<_> = fs::read_dir(&dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        file_names.sort();
+        assert_eq!(file_names.len(), 3);
+        assert!(file_names.iter().all(|file_name| is_sink_file(file_name, "file-test")));
+
+        let mut names = Vec::new();
+        for file_name in &file_names {
+            let contents = fs::read(dir.join(file_name)).unwrap();
+            let mut frames = DecodeFrames::new(&contents);
+            let handshake = DecodeHandshake::new(frames.next().unwrap()).unwrap();
+            assert_eq!(handshake.session.app_name, "file-test");
+            for frame in frames.by_ref() {
+                for event in DecodeEvents::new(frame) {
+                    if let DecodeEventBody::CreateSource(create_source) = event.body {
+                        names.push(create_source.name().unwrap().to_string());
+                    }
+                }
+            }
+            assert!(frames.remainder().is_empty());
+        }
+        // The oldest files were deleted, and what's left is the most recent components in order.
+        assert!(!names.is_empty() && names.len() < 10);
+        let expected: Vec<_> = (10 - names.len()..10).map(|i| format!("component-{i}")).collect();
+        assert_eq!(names, expected);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_compressed_file() {
+        let dir = std::env::temp_dir().join(format!("probius-file-sink-{}", fastrand::u64(..)));
+        let sink = FileSink::new(&dir).compression(FrameCompression::Lz);
+        let probius = ProbiusConfig::new().build_with_sink(sink).unwrap();
+
+        for i in 0..100 {
+            drop(probius.new_component(&format!("compressed-component-{i}")));
+        }
+        probius.flush();
+        probius.shutdown();
+
+        let file_name = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().file_name();
+        let contents = fs::read(dir.join(file_name)).unwrap();
+        let mut frames = DecodeFrames::new(&contents);
+        let handshake = DecodeHandshake::new(frames.next().unwrap()).unwrap();
+        assert_eq!(handshake.compression, FrameCompression::Lz);
+
+        let mut names = Vec::new();
+        let mut buf = Vec::new();
+        let mut frames_len = 0;
+        let mut decompressed_len = 0;
+        for frame in frames {
+            let events = handshake.decode_frame(frame, &mut buf).unwrap();
+            frames_len += frame.len();
+            decompressed_len += events.len();
+            for event in DecodeEvents::new(events) {
+                if let DecodeEventBody::CreateSource(create_source) = event.body {
+                    names.push(create_source.name().unwrap().to_string());
+                }
+            }
+        }
+        let expected: Vec<_> = (0..100).map(|i| format!("compressed-component-{i}")).collect();
+        assert_eq!(names, expected);
+        assert!(frames_len < decompressed_len);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_sink_file() {
+        assert!(is_sink_file("app-1700000000000-000001.probius", "app"));
+        assert!(!is_sink_file("app-x-1700000000000-000001.probius", "app"));
+        assert!(!is_sink_file("app-1700000000000-000001.log", "app"));
+        assert!(!is_sink_file("other-1700000000000-000001.probius", "app"));
+    }
+}
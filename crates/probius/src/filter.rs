@@ -0,0 +1,247 @@
+use core::{
+    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+use std::sync::RwLock;
+
+#[cfg(feature = "enabled")]
+use crate::component::SourcePath;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+// Bumped whenever FILTER changes so that sources know to re-evaluate it.
+static FILTER_GENERATION: AtomicU64 = AtomicU64::new(0);
+static FILTER: RwLock<Option<Filter>> = RwLock::new(None);
+
+/// Turn all tracing in this process on or off at runtime. Tracing is on by default.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Replace the process-wide filter that decides which trace sources are traced.
+pub fn set_filter(filter: Filter) {
+    *FILTER.write().expect("probius filter lock") = Some(filter);
+    FILTER_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Remove the process-wide filter so that every trace source is traced again.
+pub fn clear_filter() {
+    *FILTER.write().expect("probius filter lock") = None;
+    FILTER_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A list of rules enabling or disabling trace sources by path.
+///
+/// The path of a source is the names of its enclosing components and its own name, joined by
+/// `/`. A rule applies to a source if its pattern matches the source's path or the path of one of
+/// its enclosing components. Patterns may use `*` to match any run of characters other than `/`
+/// and `?` to match any single character other than `/`. The last rule that applies wins, and
+/// sources that no rule applies to are enabled.
+///
+/// Components are only checked when they're created. A disabled component creates no source, but
+/// the sources in it still have its name in their paths.
+///
+/// Filters can also be parsed from a comma-separated list of patterns, where patterns prefixed
+/// with `-` disable sources, e.g. `-*,server/db`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Filter {
+    rules: Vec<FilterRule>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct FilterRule {
+    pattern: String,
+    enable: bool,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enable(mut self, pattern: &str) -> Self {
+        self.rules.push(FilterRule { pattern: pattern.to_string(), enable: true });
+        self
+    }
+
+    pub fn disable(mut self, pattern: &str) -> Self {
+        self.rules.push(FilterRule { pattern: pattern.to_string(), enable: false });
+        self
+    }
+
+    pub fn is_enabled(&self, path: &str) -> bool {
+        self.rules.iter().rev()
+            .find(|rule| {
+                // Try the full path and then each enclosing component's path.
+                let mut path = path;
+                loop {
+                    if glob_match(rule.pattern.as_bytes(), path.as_bytes()) {
+                        return true;
+                    }
+                    match path.rfind('/') {
+                        Some(i) => path = &path[..i],
+                        None => return false,
+                    }
+                }
+            })
+            .is_none_or(|rule| rule.enable)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = core::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = Filter::new();
+        for pattern in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            filter = match pattern.strip_prefix('-') {
+                Some(pattern) => filter.disable(pattern),
+                None => filter.enable(pattern),
+            };
+        }
+        Ok(filter)
+    }
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text position it is currently matched up to.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some(b'?') if text[t] != b'/' => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            Some(c) if *c == text[t] => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            _ => { }
+        }
+
+        // Mismatch - let the last `*` consume one more character, unless that would cross a `/`.
+        match backtrack {
+            Some((star_p, star_t)) if text[star_t] != b'/' => {
+                backtrack = Some((star_p, star_t + 1));
+                p = star_p + 1;
+                t = star_t + 1;
+            }
+            _ => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// Whether a source at `path` is traced under the current filter, for sources that only check
+/// once, when they're created.
+#[cfg(feature = "enabled")]
+pub(crate) fn is_path_enabled(path: &SourcePath) -> bool {
+    FilterCache::new().is_enabled(path)
+}
+
+/// Per-source cache of the filter's verdict, so that checking whether a source is enabled only
+/// costs a couple of atomic loads unless the filter has changed.
+#[cfg(feature = "enabled")]
+pub(crate) struct FilterCache {
+    generation: core::cell::Cell<u64>,
+    is_enabled: core::cell::Cell<bool>,
+}
+
+#[cfg(feature = "enabled")]
+impl FilterCache {
+    pub fn new() -> Self {
+        Self {
+            generation: core::cell::Cell::new(u64::MAX),
+            is_enabled: core::cell::Cell::new(true),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self, path: &SourcePath) -> bool {
+        if !is_enabled() {
+            return false;
+        }
+
+        let generation = FILTER_GENERATION.load(Ordering::Relaxed);
+        if generation != self.generation.get() {
+            self.refresh(generation, path);
+        }
+        self.is_enabled.get()
+    }
+
+    #[cold]
+    fn refresh(&self, generation: u64, path: &SourcePath) {
+        let filter = FILTER.read().expect("probius filter lock");
+        self.is_enabled.set(filter.as_ref().is_none_or(|f| f.is_enabled(path.get())));
+        self.generation.set(generation);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        assert!(glob_match(b"server/*", b"server/db"));
+        assert!(!glob_match(b"server/*", b"server/db/query"));
+        assert!(glob_match(b"*/d?", b"server/db"));
+        assert!(glob_match(b"*", b""));
+
+        let filter: Filter = "-*, server/db, -server/db/noisy*".parse().unwrap();
+        assert!(!filter.is_enabled("server"));
+        assert!(!filter.is_enabled("server/http/request"));
+        assert!(filter.is_enabled("server/db"));
+        assert!(filter.is_enabled("server/db/query"));
+        assert!(!filter.is_enabled("server/db/noisy-poller"));
+        assert!(!filter.is_enabled("server/db/noisy-poller/tick"));
+
+        assert!(Filter::new().is_enabled("anything/at/all"));
+    }
+
+    #[cfg(feature = "enabled")]
+    #[test]
+    fn test_filter_existing_sources() {
+        let probius = crate::ProbiusConfig::new().build().unwrap();
+        let component = probius.new_component("filter-test-component");
+        let (nested, other) = component.enter(|| {
+            (probius.new_trace_source("nested"), probius.new_trace_source("other"))
+        });
+        let top_level = probius.new_trace_source("filter-test-source");
+        assert!(nested.is_enabled() && other.is_enabled() && top_level.is_enabled());
+
+        // Sources created before the filter pick it up on their next check.
+        set_filter("-filter-test-source, -filter-test-component/*, filter-test-component/other"
+            .parse().unwrap());
+        assert!(!nested.is_enabled());
+        assert!(other.is_enabled());
+        assert!(!top_level.is_enabled());
+
+        // Components created under the filter create no source, but their path still applies.
+        let filtered = component.enter(|| probius.new_component("filtered"));
+        assert_eq!(filtered.id().source, u64::MAX);
+        let (inner, inner_other) = filtered.enter(|| {
+            (probius.new_trace_source("inner"), probius.new_trace_source("other"))
+        });
+        assert!(!inner.is_enabled() && !inner_other.is_enabled());
+        let parent = component.enter(|| {
+            probius.enter_component("filtered", crate::component::current_id)
+        });
+        assert_eq!(parent, Some(component.id()));
+
+        clear_filter();
+        assert!(nested.is_enabled() && other.is_enabled() && top_level.is_enabled());
+    }
+}
@@ -0,0 +1,53 @@
+/// Hands the calling thread's completed buffers to the sink of the `Probius` instance it was
+/// created for.
+///
+/// Writers are per-thread, so each thread that records events should call `flush` periodically.
+#[derive(Clone)]
+pub struct ProbiusFlusher {
+    #[cfg(feature = "enabled")]
+    instance_id: u64,
+    #[cfg(feature = "enabled")]
+    buffer_sender: Option<bab::BufferQueueSender>,
+}
+
+impl ProbiusFlusher {
+    #[cfg(feature = "enabled")]
+    pub(crate) fn new(instance_id: u64, buffer_sender: bab::BufferQueueSender) -> Self {
+        Self { instance_id, buffer_sender: Some(buffer_sender) }
+    }
+
+    /// A flusher for the void sink, which releases buffers straight back to the pool.
+    #[cfg(feature = "enabled")]
+    pub(crate) fn void(instance_id: u64) -> Self {
+        Self { instance_id, buffer_sender: None }
+    }
+
+    #[cfg(not(feature = "enabled"))]
+    pub(crate) fn void() -> Self {
+        Self { }
+    }
+
+    #[cfg(feature = "enabled")]
+    pub fn flush(&self) {
+        self.send(crate::trace::flush_instance(self.instance_id));
+    }
+
+    #[cfg(feature = "enabled")]
+    pub(crate) fn send(&self, buffers: impl Iterator<Item = bab::BufferPtr>) {
+        let Some(buffer_sender) = &self.buffer_sender else {
+            for buffer in buffers {
+                unsafe { buffer.release(); }
+            }
+            return;
+        };
+
+        for buffer in buffers {
+            buffer_sender.push(buffer);
+        }
+        buffer_sender.flush();
+    }
+
+    #[cfg(not(feature = "enabled"))]
+    #[inline]
+    pub fn flush(&self) { }
+}
@@ -1,7 +1,22 @@
-pub use probius_mproto::{GlobalSourceId, MetricAggregate, SourceId};
+pub use probius_mproto::{
+    ClockAnchor, FrameCompression, GlobalSourceId, Heartbeat, MetricAggregate, SourceId,
+    UdpDatagramHeader,
+};
 
-pub use component::Component;
-pub use decode::{DecodeEvents, DecodeEvent, DecodeEventBody};
+pub use backpressure::BackpressureStats;
+pub use config::{ClockSource, ConfigError, DropPolicy, ProbiusConfig, SinkConfig};
+pub use component::{Component, ComponentContext, in_current_component, spawn};
+pub use decode::{
+    DecodeEvents, DecodeEvent, DecodeEventBody, DecodeFrames, DecodeHandshake, DecodeHandshakeReply,
+    SESSION_SOURCE, decode_udp_datagram,
+};
+pub use filter::{Filter, clear_filter, is_enabled, set_enabled, set_filter};
+pub use flusher::ProbiusFlusher;
+pub use sink::{
+    HandshakeOptions, PROTOCOL_VERSION, ProtocolFeatures, Resource, Sink, SinkHealth, SinkSession,
+    VoidSink,
+};
+pub use source_tree::{LogicalSource, LogicalSourceId, SourceTree};
 pub use void_sink::init_void_sink;
 
 #[cfg(feature = "enabled")]
@@ -9,15 +24,30 @@ pub use trace::*;
 #[cfg(not(feature = "enabled"))]
 pub use stubbed_trace::*;
 
+#[cfg(feature = "file-sink")]
+pub use file_sink::{FileSink, FsyncPolicy};
 #[cfg(feature = "tcp-sink")]
-pub use tcp_sink::{init_tcp_sink, ProbiusFlusher};
+pub use tcp_sink::{TcpSink, init_tcp_sink};
+#[cfg(feature = "udp-sink")]
+pub use udp_sink::{UdpSink, init_udp_sink};
+#[cfg(all(feature = "unix-sink", unix))]
+pub use unix_sink::{UnixDatagramSink, UnixSink, init_unix_sink};
 
+mod backpressure;
+#[cfg(feature = "enabled")]
+mod clock;
+mod compression;
 mod component;
+mod config;
 mod decode;
+mod filter;
+mod flusher;
 #[cfg(feature = "enabled")]
 mod encoding;
 #[cfg(feature = "enabled")]
 mod link_vec;
+mod sink;
+mod source_tree;
 mod void_sink;
 
 #[cfg(feature = "enabled")]
@@ -25,5 +55,13 @@ mod trace;
 #[cfg(not(feature = "enabled"))]
 mod stubbed_trace;
 
+#[cfg(feature = "file-sink")]
+mod file_sink;
+#[cfg(feature = "tcp-sink")]
+mod spool;
 #[cfg(feature = "tcp-sink")]
 mod tcp_sink;
+#[cfg(feature = "udp-sink")]
+mod udp_sink;
+#[cfg(all(feature = "unix-sink", unix))]
+mod unix_sink;
@@ -0,0 +1,948 @@
+use std::io;
+#[cfg(feature = "enabled")]
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU8, AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::FrameCompression;
+#[cfg(any(feature = "tcp-sink", all(feature = "unix-sink", unix)))]
+use crate::DecodeHandshakeReply;
+#[cfg(feature = "enabled")]
+use crate::{
+    ConfigError, Probius, ProbiusConfig, ProbiusFlusher, SESSION_SOURCE,
+    backpressure::Backpressure, clock::SessionClock, encoding, trace::AppConfig,
+};
+
+/// A destination for the buffers written by a `Probius` instance.
+///
+/// Probius runs each sink on its own thread. The thread connects the sink, hands it each buffer
+/// flushed by `ProbiusFlusher::flush` and reconnects it whenever sending fails. Register a sink
+/// with `ProbiusConfig::init_with_sink` or `ProbiusConfig::build_with_sink`.
+pub trait Sink: Send + 'static {
+    /// Bytes reserved at the start of every buffer for the sink's own framing.
+    fn headroom(&self) -> usize {
+        0
+    }
+
+    /// Connect to the destination. Called before the first buffer is sent, and again after `send`
+    /// or `flush` fails or the sink reports itself unhealthy. If this fails, it is retried with
+    /// exponential backoff.
+    fn connect(&mut self, _session: &SinkSession) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Send one buffer. `frame` starts with `headroom` bytes that the sink may fill in, followed
+    /// by encoded events.
+    ///
+    /// If this fails, the sink is reconnected and the same buffer is sent again, followed by any
+    /// others that were waiting behind it.
+    fn send(&mut self, frame: &mut [u8]) -> io::Result<()>;
+
+    /// Called after each batch of buffers has been sent.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Checked after each batch of buffers. An unhealthy sink is reconnected.
+    fn is_healthy(&self) -> bool {
+        true
+    }
+
+    /// Called once when the sink's thread exits, after the remaining buffers have been sent.
+    fn shutdown(&mut self) { }
+}
+
+/// Identifies the stream of buffers that a sink is sending.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SinkSession {
+    pub app_name: String,
+    /// Randomly generated when the sink is started.
+    pub session_id: u128,
+    pub resource: Resource,
+}
+
+impl SinkSession {
+    #[cfg(feature = "enabled")]
+    pub(crate) fn new(config: &ProbiusConfig) -> Self {
+        Self {
+            app_name: config.app_name.clone(),
+            session_id: fastrand::u128(..),
+            resource: Resource::detect(config),
+        }
+    }
+
+    /// The encoded `SinkHandshake` that sinks send ahead of the first buffer of a session.
+    pub fn handshake(&self, options: &HandshakeOptions) -> Vec<u8> {
+        let attributes = self.resource.attributes.iter()
+            .map(|(key, value)| probius_mproto::ResourceAttributeGen { key, value });
+        let handshake = probius_mproto::SinkHandshakeGen {
+            protocol_version: PROTOCOL_VERSION,
+            features: options.features().bits(),
+            expects_reply: options.expects_reply,
+            app_name: &self.app_name,
+            session_id_hi: (self.session_id >> 64) as u64,
+            session_id_lo: self.session_id as u64,
+            is_replay: options.is_replay,
+            compression: options.compression,
+            hostname: &self.resource.hostname,
+            pid: self.resource.pid,
+            executable: &self.resource.executable,
+            app_version: &self.resource.app_version,
+            probius_version: &self.resource.probius_version,
+            start_time_unix_nanos: self.resource.start_time_unix_nanos,
+            attributes: mproto::ListGen(attributes),
+        };
+        let mut handshake_buf = vec![0u8; mproto::encoded_len(&handshake)];
+        mproto::encode_value(handshake, &mut handshake_buf[..]);
+        handshake_buf
+    }
+}
+
+/// The process that a session comes from, sent in its handshake so that collectors can group and
+/// filter sessions by deployment.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Resource {
+    /// Empty if it couldn't be found.
+    pub hostname: String,
+    pub pid: u32,
+    /// The path of the running executable, or empty if it couldn't be found.
+    pub executable: String,
+    /// Set with `ProbiusConfig::app_version`.
+    pub app_version: String,
+    /// The version of probius that the session was recorded with.
+    pub probius_version: String,
+    /// When the sink was started, in nanoseconds since the Unix epoch.
+    pub start_time_unix_nanos: u64,
+    /// Set with `ProbiusConfig::resource_attribute`, in the order they were added.
+    pub attributes: Vec<(String, String)>,
+}
+
+impl Resource {
+    #[cfg(feature = "enabled")]
+    fn detect(config: &ProbiusConfig) -> Self {
+        let start_time = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            hostname: hostname().unwrap_or_default(),
+            pid: std::process::id(),
+            executable: std::env::current_exe()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            app_version: config.app_version.clone(),
+            probius_version: env!("CARGO_PKG_VERSION").to_string(),
+            start_time_unix_nanos: start_time.as_nanos() as u64,
+            attributes: config.resource_attributes.clone(),
+        }
+    }
+
+    /// The value of the first attribute named `key`.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.iter().find(|(k, _)| k == key).map(|(_, value)| value.as_str())
+    }
+}
+
+/// The machine's hostname, from the environment or, on Linux, procfs.
+#[cfg(feature = "enabled")]
+fn hostname() -> Option<String> {
+    let hostname = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())?;
+    let hostname = hostname.trim();
+    (!hostname.is_empty()).then(|| hostname.to_string())
+}
+
+/// The version of the sink protocol spoken by this version of probius. Collectors can't decode
+/// sessions with a different version.
+pub const PROTOCOL_VERSION: u16 = 4;
+
+/// Optional parts of the sink protocol, announced by sinks in their handshake and accepted by
+/// collectors in their reply.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ProtocolFeatures(u32);
+
+impl ProtocolFeatures {
+    pub const NONE: Self = Self(0);
+    /// Frames are compressed as given by the handshake's `compression`.
+    pub const COMPRESSION: Self = Self(1 << 0);
+    /// Aggregates are sent as `TraceAggregateDelta` events. Not yet sent by probius.
+    pub const AGGREGATE_DELTAS: Self = Self(1 << 1);
+    /// Strings are sent once per session and referred to by index. Not yet sent by probius.
+    pub const INTERNED_STRINGS: Self = Self(1 << 2);
+    /// The features that this version of probius can decode.
+    pub const SUPPORTED: Self = Self::COMPRESSION;
+
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// The features in `self` that aren't in `other`.
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+/// What a sink announces in its handshake besides its session.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct HandshakeOptions {
+    /// How the frames that follow are compressed.
+    pub compression: FrameCompression,
+    /// Whether the frames that follow were spooled while the collector was unreachable, rather
+    /// than live ones.
+    pub is_replay: bool,
+    /// Whether the sink waits for the collector's reply before sending any frames.
+    pub expects_reply: bool,
+}
+
+impl HandshakeOptions {
+    /// The features that a collector must accept to decode the frames that follow.
+    pub fn features(&self) -> ProtocolFeatures {
+        match self.compression {
+            FrameCompression::Uncompressed => ProtocolFeatures::NONE,
+            _ => ProtocolFeatures::COMPRESSION,
+        }
+    }
+}
+
+impl Default for HandshakeOptions {
+    fn default() -> Self {
+        Self {
+            compression: FrameCompression::Uncompressed,
+            is_replay: false,
+            expects_reply: false,
+        }
+    }
+}
+
+/// Read the collector's reply to a handshake sent with `expects_reply`, failing if it doesn't
+/// speak this version of the protocol or doesn't accept all of `required`.
+#[cfg(any(feature = "tcp-sink", all(feature = "unix-sink", unix)))]
+pub(crate) fn read_handshake_reply(
+    reader: &mut impl io::Read,
+    required: ProtocolFeatures,
+) -> io::Result<ProtocolFeatures> {
+    let mut len = [0u8; 2];
+    reader.read_exact(&mut len)?;
+    let mut frame = vec![0u8; u16::from_le_bytes(len) as usize];
+    reader.read_exact(&mut frame)?;
+
+    let Some(reply) = DecodeHandshakeReply::new(&frame) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed handshake reply"));
+    };
+    if reply.protocol_version != PROTOCOL_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("collector speaks protocol version {}", reply.protocol_version),
+        ));
+    }
+    let missing = required.difference(reply.features);
+    if missing != ProtocolFeatures::NONE {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("collector doesn't accept protocol features {missing:?}"),
+        ));
+    }
+    Ok(reply.features)
+}
+
+/// The state of a sink's thread, as reported by `Probius::sink_health`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SinkHealth {
+    Connecting,
+    Connected,
+    /// Connecting or sending failed, or the sink reported itself unhealthy. The sink will be
+    /// reconnected.
+    Disconnected,
+    Stopped,
+}
+
+/// A sink that discards everything.
+#[derive(Clone, Debug, Default)]
+pub struct VoidSink;
+
+impl Sink for VoidSink {
+    fn send(&mut self, _frame: &mut [u8]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Set up `probius`'s buffer pool for `sink` and start the sink's thread.
+#[cfg(feature = "enabled")]
+pub(crate) fn start_sink(
+    probius: &Probius,
+    config: &ProbiusConfig,
+    sink: Box<dyn Sink>,
+) -> Result<ProbiusFlusher, ConfigError> {
+    let headroom = sink.headroom();
+    let buffer_pool = config.new_buffer_pool(headroom);
+    let (buffer_sender, buffer_receiver) = bab::buffer_queue();
+    let flusher = ProbiusFlusher::new(probius.id(), buffer_sender);
+    let app_config = AppConfig::from_config(config, headroom, buffer_pool, flusher.clone());
+    let heartbeat = app_config.heartbeat(config.heartbeat_interval);
+    probius.try_init(app_config)?;
+
+    let session = SinkSession::new(config);
+    let options = SinkOptions::new(config);
+    let sink_thread = SinkThread::spawn(sink, session, buffer_receiver, options, heartbeat);
+    probius.set_sink_thread(sink_thread);
+
+    Ok(flusher)
+}
+
+/// How long `SinkThread::stop` waits past the drain deadline for the thread to exit.
+#[cfg(feature = "enabled")]
+const STOP_GRACE: Duration = Duration::from_millis(100);
+
+/// A sink's background thread, which is stopped and joined by `shutdown`.
+#[cfg(feature = "enabled")]
+pub(crate) struct SinkThread {
+    stop: SinkStop,
+    health: Arc<AtomicU8>,
+    handle: JoinHandle<()>,
+    // Disconnected when the thread exits, even by panicking.
+    exited: mpsc::Receiver<()>,
+}
+
+#[cfg(feature = "enabled")]
+impl SinkThread {
+    fn spawn(
+        mut sink: Box<dyn Sink>,
+        session: SinkSession,
+        buffer_receiver: bab::BufferQueueReceiver,
+        options: SinkOptions,
+        mut heartbeat: Heartbeat,
+    ) -> Self {
+        let stop = SinkStop::default();
+        let health = Arc::new(AtomicU8::new(SinkHealth::Connecting as u8));
+        let (exit_sender, exited) = mpsc::channel();
+        let handle = std::thread::spawn({
+            let stop = stop.clone();
+            let health = health.clone();
+            move || {
+                let _exit_sender = exit_sender;
+                run_sink(
+                    &mut *sink,
+                    &session,
+                    &buffer_receiver,
+                    &options,
+                    &stop,
+                    &health,
+                    &mut heartbeat,
+                );
+                health.store(SinkHealth::Stopped as u8, Ordering::Relaxed);
+            }
+        });
+        Self { stop, health, handle, exited }
+    }
+
+    pub fn health(&self) -> SinkHealth {
+        match self.health.load(Ordering::Relaxed) {
+            h if h == SinkHealth::Connecting as u8 => SinkHealth::Connecting,
+            h if h == SinkHealth::Connected as u8 => SinkHealth::Connected,
+            h if h == SinkHealth::Disconnected as u8 => SinkHealth::Disconnected,
+            _ => SinkHealth::Stopped,
+        }
+    }
+
+    /// Ask the thread to send what is left in its queue within `timeout`, and wait for it to exit.
+    /// A thread still stuck in the sink after that, e.g. writing to a collector that stopped
+    /// reading, is detached and left to exit once the sink gives up.
+    pub fn stop(self, timeout: Duration) {
+        let _ = self.stop.deadline.set(Instant::now() + timeout);
+        let wait = timeout.saturating_add(STOP_GRACE);
+        if let Err(RecvTimeoutError::Timeout) = self.exited.recv_timeout(wait) {
+            return;
+        }
+        let _ = self.handle.join();
+    }
+}
+
+/// The parts of `ProbiusConfig` used by the sink thread.
+#[cfg(feature = "enabled")]
+struct SinkOptions {
+    flush_interval: Duration,
+    max_retry_buffers: usize,
+    min_reconnect_backoff: Duration,
+    max_reconnect_backoff: Duration,
+}
+
+#[cfg(feature = "enabled")]
+impl SinkOptions {
+    fn new(config: &ProbiusConfig) -> Self {
+        Self {
+            flush_interval: config.flush_interval,
+            max_retry_buffers: config.max_retry_buffers(),
+            min_reconnect_backoff: config.min_reconnect_backoff,
+            max_reconnect_backoff: config.max_reconnect_backoff,
+        }
+    }
+}
+
+#[cfg(feature = "enabled")]
+fn run_sink(
+    sink: &mut dyn Sink,
+    session: &SinkSession,
+    buffer_receiver: &bab::BufferQueueReceiver,
+    options: &SinkOptions,
+    stop: &SinkStop,
+    health: &AtomicU8,
+    heartbeat: &mut Heartbeat,
+) {
+    // Buffers that failed to send, oldest first, to be resent after reconnecting.
+    let mut retry_queue = RetryQueue::new(options.max_retry_buffers);
+    let mut backoff = options.min_reconnect_backoff;
+
+    while !stop.is_expired() {
+        if sink.connect(session).is_ok() {
+            health.store(SinkHealth::Connected as u8, Ordering::Relaxed);
+            let sent_all = send_buffers(
+                sink,
+                buffer_receiver,
+                options,
+                stop,
+                &mut retry_queue,
+                &mut backoff,
+                heartbeat,
+            );
+            if sent_all {
+                return;
+            }
+        }
+
+        health.store(SinkHealth::Disconnected as u8, Ordering::Relaxed);
+        stop.sleep(backoff);
+        backoff = (backoff * 2).min(options.max_reconnect_backoff);
+    }
+
+    // Shutdown timed out - drop whatever is left.
+    retry_queue.clear();
+    while let Some(buffers) = try_recv(buffer_receiver) {
+        for buffer in buffers {
+            unsafe { buffer.release(); }
+        }
+    }
+    sink.shutdown();
+}
+
+/// Send buffers to a connected sink until it fails, returning false, or until shutdown has been
+/// requested and everything has been sent, returning true.
+#[cfg(feature = "enabled")]
+fn send_buffers(
+    sink: &mut dyn Sink,
+    buffer_receiver: &bab::BufferQueueReceiver,
+    options: &SinkOptions,
+    stop: &SinkStop,
+    retry_queue: &mut RetryQueue,
+    backoff: &mut Duration,
+    heartbeat: &mut Heartbeat,
+) -> bool {
+    while !stop.is_expired() {
+        while let Some(buffer) = retry_queue.front() {
+            if send_buffer(sink, buffer).is_err() {
+                return false;
+            }
+            retry_queue.pop_front();
+            heartbeat.record_sent_buffer();
+        }
+
+        if heartbeat.send_if_due(sink, retry_queue).is_err() {
+            return false;
+        }
+
+        let Some(mut buffers) = try_recv(buffer_receiver) else {
+            if stop.is_stopping() {
+                // Everything has been sent.
+                sink.shutdown();
+                return true;
+            }
+            std::thread::sleep(options.flush_interval);
+            continue;
+        };
+
+        while let Some(buffer) = buffers.next() {
+            if send_buffer(sink, buffer).is_err() {
+                retry_queue.push(buffer);
+                for buffer in buffers {
+                    retry_queue.push(buffer);
+                }
+                return false;
+            }
+            heartbeat.record_sent_buffer();
+        }
+
+        if sink.flush().is_err() || !sink.is_healthy() {
+            return false;
+        }
+        *backoff = options.min_reconnect_backoff;
+    }
+    false
+}
+
+/// Send one buffer and release it, unless sending failed.
+#[cfg(feature = "enabled")]
+fn send_buffer(sink: &mut dyn Sink, buffer: bab::BufferPtr) -> io::Result<()> {
+    let buffer_len = bab::WriterFlushSender::get_complete_buffer_len(buffer) as usize;
+    let frame = unsafe { buffer.slice_mut(0..buffer_len) };
+    sink.send(frame)?;
+    unsafe { buffer.release(); }
+    Ok(())
+}
+
+/// Buffers waiting to be resent, bounded so that a sink that stays down can't hold on to the
+/// whole buffer pool.
+#[cfg(feature = "enabled")]
+struct RetryQueue {
+    buffers: VecDeque<bab::BufferPtr>,
+    max_len: usize,
+    // Buffers released unsent because the queue was full.
+    dropped: u64,
+}
+
+#[cfg(feature = "enabled")]
+impl RetryQueue {
+    fn new(max_len: usize) -> Self {
+        Self { buffers: VecDeque::with_capacity(max_len), max_len, dropped: 0 }
+    }
+
+    fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    fn front(&self) -> Option<bab::BufferPtr> {
+        self.buffers.front().copied()
+    }
+
+    fn pop_front(&mut self) {
+        self.buffers.pop_front();
+    }
+
+    /// Queue `buffer` to be resent, dropping the oldest buffer if the queue is full.
+    fn push(&mut self, buffer: bab::BufferPtr) {
+        if self.buffers.len() == self.max_len {
+            self.dropped += 1;
+            let Some(oldest) = self.buffers.pop_front() else {
+                // Retrying is disabled.
+                unsafe { buffer.release(); }
+                return;
+            };
+            unsafe { oldest.release(); }
+        }
+        self.buffers.push_back(buffer);
+    }
+
+    fn clear(&mut self) {
+        for buffer in self.buffers.drain(..) {
+            unsafe { buffer.release(); }
+        }
+    }
+}
+
+/// Sends a session's `Heartbeat` events from its sink thread, so that collectors can tell a quiet
+/// session from one that stalled or lost its sink. Heartbeats don't take buffers from the pool, so
+/// they still go out when it's exhausted.
+#[cfg(feature = "enabled")]
+pub(crate) struct Heartbeat {
+    // Zero disables heartbeats.
+    interval: Duration,
+    next: Instant,
+    clock: SessionClock,
+    backpressure: Arc<Backpressure>,
+    live_sources: Arc<AtomicU64>,
+    sent_buffers: u64,
+    frame: Vec<u8>,
+}
+
+#[cfg(feature = "enabled")]
+impl Heartbeat {
+    pub fn new(
+        interval: Duration,
+        clock: SessionClock,
+        backpressure: Arc<Backpressure>,
+        live_sources: Arc<AtomicU64>,
+    ) -> Self {
+        Self {
+            interval,
+            next: Instant::now() + interval,
+            clock,
+            backpressure,
+            live_sources,
+            sent_buffers: 0,
+            frame: Vec::new(),
+        }
+    }
+
+    fn record_sent_buffer(&mut self) {
+        self.sent_buffers += 1;
+    }
+
+    /// Send and flush a heartbeat if one is due.
+    fn send_if_due(&mut self, sink: &mut dyn Sink, retry_queue: &RetryQueue) -> io::Result<()> {
+        let now = Instant::now();
+        if self.interval.is_zero() || now < self.next {
+            return Ok(());
+        }
+        self.next = now + self.interval;
+
+        let uptime_nanos = self.clock.now_nanos();
+        let stats = self.backpressure.stats();
+        let heartbeat = probius_mproto::Heartbeat {
+            uptime_nanos,
+            live_sources: self.live_sources.load(Ordering::Relaxed),
+            buffer_count: self.backpressure.buffer_count() as u32,
+            retry_buffers: retry_queue.len() as u32,
+            sent_buffers: self.sent_buffers,
+            dropped_events: stats.dropped_events,
+            dropped_buffers: stats.dropped_buffers + retry_queue.dropped,
+        };
+        let event_id = probius_mproto::EventId {
+            source: SESSION_SOURCE,
+            timestamp_nanos: uptime_nanos,
+            seq: probius_mproto::EventSeq { seq: 0 },
+        };
+
+        self.frame.clear();
+        self.frame.resize(sink.headroom(), 0);
+        let kind = probius_mproto::EventKind::Heartbeat;
+        encoding::encode_event(&mut self.frame, event_id, kind, heartbeat);
+        sink.send(&mut self.frame)?;
+        sink.flush()
+    }
+}
+
+/// Tells a sink thread when it should stop.
+#[cfg(feature = "enabled")]
+#[derive(Clone, Default)]
+struct SinkStop {
+    deadline: Arc<OnceLock<Instant>>,
+}
+
+#[cfg(feature = "enabled")]
+impl SinkStop {
+    /// Whether shutdown was requested. The thread should exit once its queue is empty.
+    fn is_stopping(&self) -> bool {
+        self.deadline.get().is_some()
+    }
+
+    /// Whether the time given to drain the queue has run out. The thread should release any
+    /// remaining buffers and exit.
+    fn is_expired(&self) -> bool {
+        self.deadline.get().is_some_and(|deadline| Instant::now() >= *deadline)
+    }
+
+    /// Sleep for `duration`, or until the drain deadline if that's sooner.
+    fn sleep(&self, duration: Duration) {
+        let duration = match self.deadline.get() {
+            Some(deadline) => duration.min(deadline.saturating_duration_since(Instant::now())),
+            None => duration,
+        };
+        std::thread::sleep(duration);
+    }
+}
+
+/// Take everything in `receiver`'s queue without waiting.
+#[cfg(feature = "enabled")]
+fn try_recv(receiver: &bab::BufferQueueReceiver) -> Option<bab::BufferQueueReceiveIterator> {
+    let recv = core::pin::pin!(receiver.recv());
+    let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+    match recv.poll(&mut cx) {
+        core::task::Poll::Ready(buffers) => Some(buffers),
+        core::task::Poll::Pending => None,
+    }
+}
+
+#[cfg(all(test, feature = "enabled"))]
+pub(crate) mod test {
+    use std::sync::{Condvar, Mutex, MutexGuard, atomic::AtomicUsize};
+
+    use super::*;
+    use crate::{DecodeEventBody, DecodeEvents, Probius};
+
+    /// How long tests wait for a sink's thread before failing.
+    pub(crate) const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Wait on `condvar` while `condition` holds, failing the test after `WAIT_TIMEOUT`.
+    fn wait_while<'a, T>(
+        condvar: &Condvar,
+        guard: MutexGuard<'a, T>,
+        condition: impl FnMut(&mut T) -> bool,
+    ) -> MutexGuard<'a, T> {
+        let (guard, result) = condvar.wait_timeout_while(guard, WAIT_TIMEOUT, condition).unwrap();
+        assert!(!result.timed_out(), "timed out waiting for the sink thread");
+        guard
+    }
+
+    /// Lets the built-in sinks' tests wait for a sink's thread to get somewhere, rather than
+    /// sleeping.
+    #[cfg(any(feature = "tcp-sink", feature = "file-sink", all(feature = "unix-sink", unix)))]
+    pub(crate) mod watch {
+        use super::*;
+
+        /// The calls that a `WatchedSink` has returned from so far.
+        #[derive(Copy, Clone, Debug, Default)]
+        pub(crate) struct SinkCalls {
+            pub connects: usize,
+            pub sends: usize,
+        }
+
+        /// Counts the calls made to the sinks that it watches.
+        #[derive(Clone, Default)]
+        pub(crate) struct SinkWatch {
+            calls: Arc<(Mutex<SinkCalls>, Condvar)>,
+        }
+
+        impl SinkWatch {
+            pub fn watch<S: Sink>(&self, sink: S) -> WatchedSink<S> {
+                WatchedSink { sink, watch: self.clone() }
+            }
+
+            /// Wait until `is_done` holds for the calls made so far, and return them.
+            pub fn wait_for(&self, is_done: impl Fn(&SinkCalls) -> bool) -> SinkCalls {
+                let (calls, call_returned) = &*self.calls;
+                *wait_while(call_returned, calls.lock().unwrap(), |calls| !is_done(calls))
+            }
+
+            fn record(&self, f: impl FnOnce(&mut SinkCalls)) {
+                let (calls, call_returned) = &*self.calls;
+                f(&mut calls.lock().unwrap());
+                call_returned.notify_all();
+            }
+        }
+
+        /// Forwards to `sink`, counting the calls that it returns from, whether they fail or not.
+        pub(crate) struct WatchedSink<S> {
+            sink: S,
+            watch: SinkWatch,
+        }
+
+        impl<S: Sink> Sink for WatchedSink<S> {
+            fn headroom(&self) -> usize {
+                self.sink.headroom()
+            }
+
+            fn connect(&mut self, session: &SinkSession) -> io::Result<()> {
+                let result = self.sink.connect(session);
+                self.watch.record(|calls| calls.connects += 1);
+                result
+            }
+
+            fn send(&mut self, frame: &mut [u8]) -> io::Result<()> {
+                let result = self.sink.send(frame);
+                self.watch.record(|calls| calls.sends += 1);
+                result
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.sink.flush()
+            }
+
+            fn is_healthy(&self) -> bool {
+                self.sink.is_healthy()
+            }
+
+            fn shutdown(&mut self) {
+                self.sink.shutdown();
+            }
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct CollectSink {
+        frames: Arc<Mutex<Vec<Vec<u8>>>>,
+        // Notified whenever a frame is added to `frames`.
+        frame_sent: Arc<Condvar>,
+        connects: Arc<Mutex<Vec<SinkSession>>>,
+        // How many of the next sends should fail.
+        failing_sends: Arc<AtomicUsize>,
+    }
+
+    impl CollectSink {
+        fn source_names(&self) -> Vec<String> {
+            source_names(&self.frames.lock().unwrap())
+        }
+
+        /// Wait for the sink thread to send frames until `is_done` holds for all of them.
+        fn wait_for(&self, is_done: impl Fn(&[Vec<u8>]) -> bool) {
+            let frames = self.frames.lock().unwrap();
+            let _frames = wait_while(&self.frame_sent, frames, |frames| !is_done(frames));
+        }
+    }
+
+    fn source_names(frames: &[Vec<u8>]) -> Vec<String> {
+        frames.iter()
+            .flat_map(|frame| DecodeEvents::new(frame).collect::<Vec<_>>())
+            .filter_map(|event| match event.body {
+                DecodeEventBody::CreateSource(create_source) => {
+                    Some(create_source.name().unwrap().to_string())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    impl Sink for CollectSink {
+        fn headroom(&self) -> usize {
+            4
+        }
+
+        fn connect(&mut self, session: &SinkSession) -> io::Result<()> {
+            self.connects.lock().unwrap().push(session.clone());
+            Ok(())
+        }
+
+        fn send(&mut self, frame: &mut [u8]) -> io::Result<()> {
+            let failing = self.failing_sends
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+            if failing.is_ok() {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            self.frames.lock().unwrap().push(frame[self.headroom()..].to_vec());
+            self.frame_sent.notify_all();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_sink() {
+        let sink = CollectSink::default();
+        let probius = ProbiusConfig::new()
+            .app_name("custom-sink")
+            .build_with_sink(sink.clone())
+            .unwrap();
+
+        drop(probius.new_component("custom"));
+        probius.flush();
+        probius.shutdown();
+        assert_eq!(probius.sink_health(), None);
+
+        let connects = sink.connects.lock().unwrap();
+        assert_eq!(connects.len(), 1);
+        assert_eq!(connects[0].app_name, "custom-sink");
+
+        assert_eq!(sink.source_names(), ["custom"]);
+    }
+
+    #[test]
+    fn test_shutdown_flushes_other_threads() {
+        let sink = CollectSink::default();
+        let probius = ProbiusConfig::new()
+            .buffer_size(1024)
+            .build_with_sink(sink.clone())
+            .unwrap();
+
+        // Each name fills most of a buffer, so writing the second completes the first one's.
+        let names: Vec<_> = (0..2).map(|i| format!("{i}-{}", "x".repeat(600))).collect();
+        let (written_sender, written) = std::sync::mpsc::channel();
+        let (done_sender, done) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::spawn({
+            let probius = probius.clone();
+            let names = names.clone();
+            move || {
+                for name in &names {
+                    drop(probius.new_component(name));
+                }
+                written_sender.send(()).unwrap();
+                // Outlive the shutdown without flushing.
+                let _ = done.recv();
+            }
+        });
+        written.recv().unwrap();
+        probius.shutdown();
+        assert_eq!(sink.source_names(), names[..1]);
+
+        drop(done_sender);
+        thread.join().unwrap();
+    }
+
+    fn send_components(
+        probius: &Probius,
+        sink: &CollectSink,
+        names: impl Iterator<Item = String>,
+    ) {
+        for name in names {
+            drop(probius.new_component(&name));
+            probius.flush();
+            // Wait for the sink thread so that each flush gets its own frame.
+            sink.wait_for(|frames| source_names(frames).last() == Some(&name));
+        }
+    }
+
+    #[test]
+    fn test_resend_after_reconnect() {
+        let sink = CollectSink::default();
+        let probius = ProbiusConfig::new()
+            .reconnect_backoff(Duration::from_millis(1), Duration::from_millis(4))
+            .build_with_sink(sink.clone())
+            .unwrap();
+
+        send_components(&probius, &sink, (0..3).map(|i| format!("component-{i}")));
+        sink.failing_sends.store(3, Ordering::Relaxed);
+        send_components(&probius, &sink, (3..6).map(|i| format!("component-{i}")));
+        probius.shutdown();
+
+        // Every buffer arrives once and in order, despite the failed sends.
+        let expected: Vec<_> = (0..6).map(|i| format!("component-{i}")).collect();
+        assert_eq!(sink.source_names(), expected);
+        assert_eq!(sink.connects.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_heartbeats() {
+        fn heartbeats(frames: &[Vec<u8>]) -> Vec<probius_mproto::Heartbeat> {
+            frames.iter()
+                .flat_map(|frame| DecodeEvents::new(frame).collect::<Vec<_>>())
+                .filter_map(|event| match event.body {
+                    DecodeEventBody::Heartbeat(heartbeat) => Some(heartbeat),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        let sink = CollectSink::default();
+        let probius = ProbiusConfig::new()
+            .heartbeat_interval(Duration::from_millis(10))
+            .build_with_sink(sink.clone())
+            .unwrap();
+
+        let _live = probius.new_trace_source("live");
+        drop(probius.new_component("dropped"));
+        probius.flush();
+        // Heartbeats keep coming while nothing else happens.
+        sink.wait_for(|frames| {
+            heartbeats(frames).iter().filter(|heartbeat| heartbeat.sent_buffers >= 1).count() >= 3
+        });
+        probius.shutdown();
+
+        let heartbeats = heartbeats(&sink.frames.lock().unwrap());
+        assert!(heartbeats.windows(2).all(|pair| pair[0].uptime_nanos < pair[1].uptime_nanos));
+        let last = heartbeats.last().unwrap();
+        assert_eq!(last.live_sources, 1);
+        assert_eq!(last.buffer_count, 256);
+        assert!(last.sent_buffers >= 1);
+        assert_eq!((last.dropped_events, last.dropped_buffers), (0, 0));
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use crate::{DecodeEvent, DecodeEventBody, MetricAggregate, SourceId};
+
+/// Index of a logical source in a `SourceTree`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct LogicalSourceId(pub usize);
+
+/// A node in the collector's view of a session's component tree.
+///
+/// Sources with the same name and logical parent are folded into a single logical source that
+/// tracks how many instances are live and how long they lived, e.g. one for every time a
+/// component is entered. Recurring and ephemeral sources are folded separately.
+#[derive(Clone, Debug)]
+pub struct LogicalSource {
+    pub name: String,
+    pub parent: Option<LogicalSourceId>,
+    pub is_recurring: bool,
+    pub live_instances: u64,
+    pub total_instances: u64,
+    /// Lifetimes of deleted instances, in nanoseconds.
+    pub lifetime_nanos: MetricAggregate,
+}
+
+/// Builds the logical source tree of a single session from its decoded events.
+#[derive(Default)]
+pub struct SourceTree {
+    sources: Vec<LogicalSource>,
+    // Live instances, mapped to their logical source and creation timestamp.
+    instances: HashMap<SourceId, (LogicalSourceId, u64)>,
+    // Logical sources by parent, name and whether they're recurring.
+    folded: HashMap<(Option<LogicalSourceId>, String, bool), LogicalSourceId>,
+}
+
+impl SourceTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the tree with a decoded event. Events other than `CreateSource` and `DeleteSource`
+    /// are ignored.
+    pub fn ingest(&mut self, event: &DecodeEvent) {
+        match &event.body {
+            DecodeEventBody::CreateSource(create_source) => {
+                let (Ok(name), Ok(parent), Ok(is_recurring)) = (
+                    create_source.name(),
+                    create_source.parent(),
+                    create_source.is_recurring(),
+                ) else {
+                    return;
+                };
+                // A parent that has already been deleted (or was never seen) is treated as the
+                // root.
+                let parent = parent
+                    .and_then(|p| SourceId::try_from(p).ok())
+                    .and_then(|p| self.logical_id(p));
+
+                let key = (parent, name.to_string(), is_recurring);
+                let logical_id = match self.folded.get(&key) {
+                    Some(id) => *id,
+                    None => {
+                        let id = self.push_source(name, parent, is_recurring);
+                        self.folded.insert(key, id);
+                        id
+                    }
+                };
+
+                let source = &mut self.sources[logical_id.0];
+                source.live_instances += 1;
+                source.total_instances += 1;
+                self.instances.insert(event.id.source, (logical_id, event.id.timestamp_nanos));
+            }
+            DecodeEventBody::DeleteSource => {
+                let Some((logical_id, create_nanos)) = self.instances.remove(&event.id.source)
+                else {
+                    return;
+                };
+
+                let source = &mut self.sources[logical_id.0];
+                source.live_instances = source.live_instances.saturating_sub(1);
+
+                let lifetime = event.id.timestamp_nanos.saturating_sub(create_nanos) as i64;
+                let lifetime_nanos = &mut source.lifetime_nanos;
+                lifetime_nanos.count += 1;
+                lifetime_nanos.sum += lifetime;
+                lifetime_nanos.min = core::cmp::min(lifetime_nanos.min, lifetime);
+                lifetime_nanos.max = core::cmp::max(lifetime_nanos.max, lifetime);
+            }
+            _ => { }
+        }
+    }
+
+    /// Get the logical source of a live source instance.
+    pub fn logical_id(&self, source: SourceId) -> Option<LogicalSourceId> {
+        self.instances.get(&source).map(|(id, _)| *id)
+    }
+
+    pub fn get(&self, id: LogicalSourceId) -> Option<&LogicalSource> {
+        self.sources.get(id.0)
+    }
+
+    pub fn sources(&self) -> impl ExactSizeIterator<Item = (LogicalSourceId, &LogicalSource)> {
+        self.sources.iter().enumerate().map(|(i, s)| (LogicalSourceId(i), s))
+    }
+
+    fn push_source(
+        &mut self,
+        name: &str,
+        parent: Option<LogicalSourceId>,
+        is_recurring: bool,
+    ) -> LogicalSourceId {
+        let id = LogicalSourceId(self.sources.len());
+        self.sources.push(LogicalSource {
+            name: name.to_string(),
+            parent,
+            is_recurring,
+            live_instances: 0,
+            total_instances: 0,
+            lifetime_nanos: MetricAggregate {
+                count: 0,
+                sum: 0,
+                min: i64::MAX,
+                max: i64::MIN,
+            },
+        });
+        id
+    }
+}
+
+#[cfg(all(test, feature = "enabled"))]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{DecodeEvents, backpressure::Backpressure, encoding::ProbiusWriter};
+
+    fn event_id(source: u64, timestamp_nanos: u64) -> probius_mproto::EventId {
+        probius_mproto::EventId {
+            source: SourceId { source },
+            timestamp_nanos,
+            seq: probius_mproto::EventSeq { seq: 0 },
+        }
+    }
+
+    #[test]
+    fn test_fold_ephemeral_sources() {
+        let buffer_pool = bab::HeapBufferPool::new(8192, 4, 16);
+        let backpressure = Backpressure::new(crate::DropPolicy::DropNewest, buffer_pool);
+        let writer = ProbiusWriter::new(0, Arc::new(backpressure), None);
+        let root = SourceId { source: 0 };
+        writer.create_source(event_id(0, 0), "server", None, true);
+        for i in 1..=3 {
+            writer.create_source(event_id(i, 0), "connection", Some(root), false);
+        }
+        writer.create_source(event_id(4, 0), "worker", Some(root), true);
+        writer.create_source(event_id(5, 0), "worker", Some(root), true);
+        writer.delete_source(event_id(1, 100));
+        writer.delete_source(event_id(2, 300));
+
+        let mut tree = SourceTree::new();
+        for buffer in writer.flush() {
+            let len = bab::WriterFlushSender::get_complete_buffer_len(buffer) as usize;
+            for event in DecodeEvents::new(unsafe { buffer.slice(0..len) }) {
+                tree.ingest(&event);
+            }
+        }
+
+        let sources: Vec<_> = tree.sources()
+            .map(|(_, s)| (s.name.as_str(), s.live_instances, s.total_instances))
+            .collect();
+        assert_eq!(sources, vec![("server", 1, 1), ("connection", 1, 3), ("worker", 2, 2)]);
+
+        let connection = tree.logical_id(SourceId { source: 3 }).unwrap();
+        let connection = tree.get(connection).unwrap();
+        assert_eq!(connection.parent, tree.logical_id(root));
+        assert_eq!(connection.lifetime_nanos.count, 2);
+        assert_eq!(connection.lifetime_nanos.sum, 400);
+        assert_eq!(connection.lifetime_nanos.max, 300);
+    }
+
+    #[test]
+    fn test_fold_entered_components() {
+        let probius = crate::ProbiusConfig::new().build().unwrap();
+        for _ in 0..100 {
+            probius.enter_component("request", || { });
+        }
+
+        let mut tree = SourceTree::new();
+        for buffer in crate::trace::flush_instance(probius.id()) {
+            let len = bab::WriterFlushSender::get_complete_buffer_len(buffer) as usize;
+            for event in DecodeEvents::new(unsafe { buffer.slice(0..len) }) {
+                tree.ingest(&event);
+            }
+            unsafe { buffer.release(); }
+        }
+
+        let sources: Vec<_> = tree.sources()
+            .map(|(_, s)| (s.name.as_str(), s.is_recurring, s.live_instances, s.total_instances))
+            .collect();
+        assert_eq!(sources, vec![("request", true, 0, 100)]);
+        let (_, request) = tree.sources().next().unwrap();
+        assert_eq!(request.lifetime_nanos.count, 100);
+    }
+}
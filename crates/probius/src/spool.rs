@@ -0,0 +1,188 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::DecodeFrames;
+
+/// The spool is split into this many segments, so that the oldest can be deleted to make room.
+const SEGMENT_COUNT: u64 = 8;
+
+/// Length-prefixed frames that couldn't be sent, kept in a directory until they can be replayed.
+///
+/// Frames are appended to segment files named `{index}-{session id}.spool`. When the spool is
+/// full, the oldest segments are deleted. Segments left behind by a previous process are picked up
+/// and replayed too.
+pub(crate) struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+    // Oldest first. The writer appends to the last one.
+    segments: VecDeque<Segment>,
+    writer: Option<BufWriter<File>>,
+    len: u64,
+}
+
+struct Segment {
+    index: u64,
+    session_id: u128,
+    len: u64,
+}
+
+impl Segment {
+    fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{:010}-{:032x}.spool", self.index, self.session_id))
+    }
+
+    fn parse(file_name: &str, len: u64) -> Option<Self> {
+        let (index, session_id) = file_name.strip_suffix(".spool")?.split_once('-')?;
+        Some(Self {
+            index: index.parse().ok()?,
+            session_id: u128::from_str_radix(session_id, 16).ok()?,
+            len,
+        })
+    }
+}
+
+impl Spool {
+    pub fn open(dir: &Path, max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let Some(file_name) = entry.file_name().into_string().ok() else {
+                continue;
+            };
+            if let Some(segment) = Segment::parse(&file_name, entry.metadata()?.len()) {
+                segments.push(segment);
+            }
+        }
+        segments.sort_by_key(|segment| segment.index);
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            len: segments.iter().map(|segment| segment.len).sum(),
+            segments: segments.into(),
+            writer: None,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append `frame`, deleting the oldest segments if the spool would grow past its size cap.
+    /// The frame is dropped if it doesn't fit even then.
+    pub fn push(&mut self, session_id: u128, frame: &[u8]) -> io::Result<()> {
+        let frame_len = frame.len() as u64;
+        let segment_max_bytes = self.max_bytes / SEGMENT_COUNT;
+        let start_segment = match (self.segments.back(), &self.writer) {
+            (Some(segment), Some(_)) => {
+                segment.session_id != session_id
+                    || (segment.len > 0 && segment.len + frame_len > segment_max_bytes)
+            }
+            _ => true,
+        };
+        if start_segment {
+            self.start_segment(session_id)?;
+        }
+
+        while self.len + frame_len > self.max_bytes && self.segments.len() > 1 {
+            let Some(oldest) = self.segments.pop_front() else {
+                break;
+            };
+            fs::remove_file(oldest.path(&self.dir))?;
+            self.len -= oldest.len;
+        }
+        if self.len + frame_len > self.max_bytes {
+            return Ok(());
+        }
+
+        let (Some(writer), Some(segment)) = (&mut self.writer, self.segments.back_mut()) else {
+            return Err(io::ErrorKind::NotFound.into());
+        };
+        writer.write_all(frame)?;
+        segment.len += frame_len;
+        self.len += frame_len;
+        Ok(())
+    }
+
+    fn start_segment(&mut self, session_id: u128) -> io::Result<()> {
+        self.flush()?;
+        self.writer = None;
+
+        let index = self.segments.back().map_or(0, |segment| segment.index + 1);
+        let segment = Segment { index, session_id, len: 0 };
+        let file = File::options().create_new(true).append(true).open(segment.path(&self.dir))?;
+        self.writer = Some(BufWriter::new(file));
+        self.segments.push_back(segment);
+        Ok(())
+    }
+
+    /// Pass the frames of each segment, oldest first, to `send` along with the id of the session
+    /// they were spooled by. Segments are deleted once they have been sent. If `send` fails, the
+    /// segment it was given is kept to be replayed again.
+    pub fn replay(
+        &mut self,
+        mut send: impl FnMut(u128, &[u8]) -> io::Result<()>,
+    ) -> io::Result<()> {
+        self.flush()?;
+        self.writer = None;
+
+        while let Some(segment) = self.segments.front() {
+            let path = segment.path(&self.dir);
+            let contents = fs::read(&path)?;
+            // A frame that was cut off when a previous process exited can't be replayed.
+            let mut frames = DecodeFrames::new(&contents);
+            frames.by_ref().for_each(drop);
+            let complete_len = contents.len() - frames.remainder().len();
+
+            send(segment.session_id, &contents[..complete_len])?;
+            fs::remove_file(&path)?;
+            self.len -= segment.len;
+            self.segments.pop_front();
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_spool_size_cap() {
+        let dir = std::env::temp_dir().join(format!("probius-spool-{}", fastrand::u64(..)));
+        let mut spool = Spool::open(&dir, 8 * 100).unwrap();
+        let frame = |i: u8| [98u16.to_le_bytes().as_slice(), &[i; 98]].concat();
+        for i in 0..20 {
+            spool.push(1, &frame(i)).unwrap();
+        }
+        spool.flush().unwrap();
+        drop(spool);
+
+        // Reopened, only the newest frames that fit are left.
+        let mut spool = Spool::open(&dir, 8 * 100).unwrap();
+        let mut replayed = Vec::new();
+        spool.replay(|session_id, frames| {
+            assert_eq!(session_id, 1);
+            replayed.extend(DecodeFrames::new(frames).map(|frame| frame[0]));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(replayed, (12..20).collect::<Vec<_>>());
+        assert!(spool.is_empty());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,18 +2,26 @@ use core::future::Future;
 
 use probius_mproto::SourceId;
 
-use crate::Component;
+use crate::{BackpressureStats, ClockSource, Component, SinkHealth};
 
 #[inline]
 pub fn flush() -> impl Iterator<Item = bab::BufferPtr> {
     [].into_iter()
 }
 
+#[inline]
+pub fn shutdown() { }
+
 #[inline]
 pub fn new_component(_name: &str) -> Component {
     Component::new()
 }
 
+#[inline]
+pub fn new_component_ephemeral(_name: &str) -> Component {
+    Component::new()
+}
+
 #[inline]
 pub fn enter_component<R>(_name: &str, f: impl FnOnce() -> R) -> R {
     f()
@@ -44,6 +52,84 @@ pub fn new_trace_source_ephemeral(_name: &str) -> TraceSource {
     TraceSource(())
 }
 
+#[derive(Clone)]
+pub struct Probius(());
+
+static GLOBAL_PROBIUS: Probius = Probius(());
+
+impl Probius {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self(())
+    }
+
+    #[inline]
+    pub fn global() -> &'static Probius {
+        &GLOBAL_PROBIUS
+    }
+
+    #[inline]
+    pub fn shutdown(&self) { }
+
+    #[inline]
+    pub fn sink_health(&self) -> Option<SinkHealth> {
+        None
+    }
+
+    #[inline]
+    pub fn backpressure_stats(&self) -> BackpressureStats {
+        BackpressureStats::default()
+    }
+
+    #[inline]
+    pub fn clock_source(&self) -> ClockSource {
+        ClockSource::Monotonic
+    }
+
+    #[inline]
+    pub fn flush(&self) { }
+
+    #[inline]
+    pub fn new_component(&self, _name: &str) -> Component {
+        Component::new()
+    }
+
+    #[inline]
+    pub fn new_component_ephemeral(&self, _name: &str) -> Component {
+        Component::new()
+    }
+
+    #[inline]
+    pub fn enter_component<R>(&self, _name: &str, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    #[inline]
+    pub fn enter_component_async<F: Future>(&self, _name: &str, f: F) -> F {
+        f
+    }
+
+    #[inline]
+    pub fn enter_component_ephemeral<R>(&self, _name: &str, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    #[inline]
+    pub fn enter_component_ephemeral_async<F: Future>(&self, _name: &str, f: F) -> F {
+        f
+    }
+
+    #[inline]
+    pub fn new_trace_source(&self, _name: &str) -> TraceSource {
+        TraceSource(())
+    }
+
+    #[inline]
+    pub fn new_trace_source_ephemeral(&self, _name: &str) -> TraceSource {
+        TraceSource(())
+    }
+}
+
 pub struct Source(());
 
 impl Source {
@@ -59,6 +145,16 @@ impl Source {
 pub struct TraceSource(());
 
 impl TraceSource {
+    #[inline]
+    pub fn with_max_reentry_depth(self, _depth: u16) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn trace<R>(&self, f: impl FnOnce() -> R) -> R {
         f()
@@ -69,6 +165,34 @@ impl TraceSource {
         f
     }
 
+    #[inline]
+    pub fn trace_result<T, E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+        f()
+    }
+
+    #[inline]
+    pub fn trace_result_tagged<T, E>(
+        &self,
+        f: impl FnOnce() -> Result<T, E>,
+        _variant: impl FnOnce(&E) -> &'static str,
+    ) -> Result<T, E> {
+        f()
+    }
+
+    #[inline]
+    pub fn trace_future_result<T, E, F: Future<Output = Result<T, E>>>(&self, f: F) -> F {
+        f
+    }
+
+    #[inline]
+    pub fn trace_future_result_tagged<T, E, F: Future<Output = Result<T, E>>>(
+        &self,
+        f: F,
+        _variant: impl FnOnce(&E) -> &'static str,
+    ) -> F {
+        f
+    }
+
     #[inline]
     pub fn flush_aggregate_full(&self) {
     }
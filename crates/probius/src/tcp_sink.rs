@@ -1,86 +1,432 @@
 use std::{
-    io::Write,
+    io::{self, Write},
     net::{TcpStream, ToSocketAddrs},
-    time::Duration,
+    path::PathBuf,
+    time::{Duration, Instant},
 };
 
+use crate::{
+    FrameCompression, HandshakeOptions, ProbiusConfig, ProbiusFlusher, Sink, SinkSession,
+    compression::FrameEncoder, sink::read_handshake_reply, spool::Spool,
+};
+
+/// Initialize probius with a TCP sink streaming to `remote_addr`. Panics if probius was already
+/// initialized.
 pub fn init_tcp_sink(
     app_name: &str,
     remote_addr: impl ToSocketAddrs + Send + 'static,
 ) -> ProbiusFlusher {
-    let (buffer_sender, mut buffer_receiver) = bab::buffer_queue();
-
-    let headroom = 2; // headroom for u16 length prefix
-    let buffer_size = 8192 - headroom;
-    let buffer_pool = bab::HeapBufferPool::new(buffer_size, 16, 64);
-    crate::init(headroom, buffer_pool.clone());
-
-    // Build handshake payload
-    let handshake = probius_mproto::SinkHandshakeGen {
-        app_name,
-        session_id_hi: fastrand::u64(..),
-        session_id_lo: fastrand::u64(..),
-    };
-    let handshake_len = mproto::encoded_len(&handshake);
-    let mut handshake_buf = vec![0u8; 2 + handshake_len];
-    handshake_buf[..2].copy_from_slice(&(handshake_len as u16).to_le_bytes());
-    mproto::encode_value(handshake, &mut handshake_buf[2..]);
-
-    std::thread::spawn(move || {
-        loop {
-            println!("Retrying probius tcp");
-            let Ok(mut stream) = TcpStream::connect(&remote_addr) else {
-                std::thread::sleep(Duration::from_secs(1));
-                continue;
-            };
-            println!("Reconnected probius tcp");
+    let config = ProbiusConfig::new().app_name(app_name).buffer_count(1024);
+    match config.init_with_sink(TcpSink::new(remote_addr)) {
+        Ok(flusher) => flusher,
+        Err(e) => panic!("probius::init_tcp_sink: {e}"),
+    }
+}
 
-            // Perform handshake
-            if let Err(_) = stream.write_all(&handshake_buf[..]) {
-                todo!();
-            }
+/// How often a sink with a spool tries to reach the collector again after losing it.
+const SPOOL_RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Streams buffers to a collector over TCP, each prefixed with its u16 length, after a
+/// length-prefixed `SinkHandshake`.
+///
+/// With a `spool`, buffers that can't be sent while the collector is unreachable are written to
+/// disk instead of being held in memory. Once the collector is back, they are replayed in order
+/// over a separate connection whose handshake is marked as a replay, before the live connection is
+/// resumed. A replay interrupted by another outage is resent from the start of its segment, so
+/// collectors may see some replayed buffers twice. Spooled buffers are replayed with the sink's
+/// current compression, so a spool left behind by a previous process should have been written
+/// with the same setting.
+pub struct TcpSink<A> {
+    remote_addr: A,
+    stream: Option<TcpStream>,
+    frame_encoder: FrameEncoder,
+    reply_timeout: Option<Duration>,
+    spool_options: Option<(PathBuf, u64)>,
+    spool: Option<Spool>,
+    session: Option<SinkSession>,
+    last_connect: Option<Instant>,
+}
+
+impl<A: ToSocketAddrs + Send + 'static> TcpSink<A> {
+    pub fn new(remote_addr: A) -> Self {
+        Self {
+            remote_addr,
+            stream: None,
+            frame_encoder: FrameEncoder::new(FrameCompression::Uncompressed),
+            reply_timeout: None,
+            spool_options: None,
+            spool: None,
+            session: None,
+            last_connect: None,
+        }
+    }
+
+    /// Compress each buffer's payload, as announced in the handshake. Defaults to
+    /// `FrameCompression::Uncompressed`.
+    pub fn compression(mut self, compression: FrameCompression) -> Self {
+        self.frame_encoder = FrameEncoder::new(compression);
+        self
+    }
+
+    /// Wait up to `reply_timeout` for the collector to reply to each handshake before sending any
+    /// buffers, and disconnect if it doesn't speak this version of the protocol or can't decode
+    /// the sink's compression.
+    pub fn negotiate(mut self, reply_timeout: Duration) -> Self {
+        self.reply_timeout = Some(reply_timeout);
+        self
+    }
+
+    /// Spool buffers to `dir` while the collector is unreachable, deleting the oldest once the
+    /// spool reaches `max_bytes`.
+    pub fn spool(mut self, dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        self.spool_options = Some((dir.into(), max_bytes));
+        self
+    }
+
+    fn connect_stream(&self, session: &SinkSession, is_replay: bool) -> io::Result<TcpStream> {
+        let mut stream = TcpStream::connect(&self.remote_addr)?;
+
+        // Perform handshake
+        let options = HandshakeOptions {
+            compression: self.frame_encoder.compression(),
+            is_replay,
+            expects_reply: self.reply_timeout.is_some(),
+        };
+        let handshake = session.handshake(&options);
+        let mut handshake_buf = Vec::with_capacity(2 + handshake.len());
+        handshake_buf.extend_from_slice(&(handshake.len() as u16).to_le_bytes());
+        handshake_buf.extend_from_slice(&handshake);
+        // On failure the connection is dropped and retried by the sink thread.
+        stream.write_all(&handshake_buf[..])?;
+
+        if let Some(reply_timeout) = self.reply_timeout {
+            stream.set_read_timeout(Some(reply_timeout))?;
+            read_handshake_reply(&mut stream, options.features())?;
+        }
 
-            buffer_receiver = pollster::block_on(async move {
-                loop {
-                    for buffer in buffer_receiver.recv().await {
-                        let buffer_len = bab::WriterFlushSender::get_complete_buffer_len(buffer) as usize;
-                        let payload_len = buffer_len - 2;
-                        let payload = unsafe { buffer.slice_mut(0..buffer_len) };
-                        payload[..2].copy_from_slice(&(payload_len as u16).to_le_bytes());
-
-                        if let Err(_e) = stream.write_all(payload) {
-                            println!("Probius connection broke");
-                            // TODO this will lose data - the current buffer and the remaining
-                            // buffers in the buffer_receiver.recv() iterator.
-                            unsafe { buffer.release(); }
-                            return buffer_receiver;
-                        }
-
-                        unsafe { buffer.release(); }
-                    }
-
-                    std::thread::sleep(Duration::from_millis(5));
+        Ok(stream)
+    }
+
+    /// Replay the spool, if there is one, then open the live connection.
+    fn reconnect(&mut self) -> io::Result<()> {
+        self.stream = None;
+        self.last_connect = Some(Instant::now());
+        let Some(session) = self.session.clone() else {
+            return Err(io::ErrorKind::NotConnected.into());
+        };
+
+        if self.spool.as_ref().is_some_and(|spool| !spool.is_empty())
+            && let Some(mut spool) = self.spool.take()
+        {
+            let mut replay_stream: Option<(u128, TcpStream)> = None;
+            let result = spool.replay(|session_id, frames| {
+                if replay_stream.as_ref().is_none_or(|(id, _)| *id != session_id) {
+                    // Segments left by a previous process are sent with this process's resource.
+                    let replay_session = SinkSession { session_id, ..session.clone() };
+                    let stream = self.connect_stream(&replay_session, true)?;
+                    replay_stream = Some((session_id, stream));
+                }
+                match &mut replay_stream {
+                    Some((_, stream)) => stream.write_all(frames),
+                    None => Err(io::ErrorKind::NotConnected.into()),
                 }
             });
-
-            std::thread::sleep(Duration::from_secs(1));
+            self.spool = Some(spool);
+            // Keep spooling until the replay has finished, so that buffers stay in order.
+            result?;
         }
-    });
 
-    ProbiusFlusher { buffer_sender }
+        self.stream = Some(self.connect_stream(&session, false)?);
+        Ok(())
+    }
 }
 
-#[derive(Clone)]
-pub struct ProbiusFlusher {
-    buffer_sender: bab::BufferQueueSender,
-}
+impl<A: ToSocketAddrs + Send + 'static> Sink for TcpSink<A> {
+    fn headroom(&self) -> usize {
+        2 // headroom for u16 length prefix
+    }
+
+    fn connect(&mut self, session: &SinkSession) -> io::Result<()> {
+        self.session = Some(session.clone());
+        if self.spool.is_none() && let Some((dir, max_bytes)) = &self.spool_options {
+            self.spool = Some(Spool::open(dir, *max_bytes)?);
+        }
+
+        match self.reconnect() {
+            Ok(()) => Ok(()),
+            // Spool until the collector is back.
+            Err(_) if self.spool.is_some() => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn send(&mut self, frame: &mut [u8]) -> io::Result<()> {
+        let frame = self.frame_encoder.encode(frame);
+
+        if let Some(stream) = &mut self.stream {
+            let Err(e) = stream.write_all(frame) else {
+                return Ok(());
+            };
+            self.stream = None;
+            if self.spool.is_none() {
+                return Err(e);
+            }
+        }
+
+        match (&mut self.spool, &self.session) {
+            (Some(spool), Some(session)) => spool.push(session.session_id, frame),
+            _ => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let Some(spool) = &mut self.spool else {
+            return Ok(());
+        };
+        spool.flush()?;
 
-impl ProbiusFlusher {
-    pub fn flush(&self) {
-        for buffer in crate::flush() {
-            self.buffer_sender.push(buffer);
+        let reconnect_due = self.last_connect
+            .is_none_or(|last_connect| last_connect.elapsed() >= SPOOL_RECONNECT_INTERVAL);
+        if self.stream.is_none() && reconnect_due {
+            let _ = self.reconnect();
+        }
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        self.stream = None;
+        if let Some(spool) = &mut self.spool {
+            let _ = spool.flush();
         }
-        self.buffer_sender.flush();
     }
 }
 
+#[cfg(test)]
+mod test {
+    use std::{io::Read, net::TcpListener};
+
+    use super::*;
+    use crate::{
+        ConfigError, DecodeEventBody, DecodeEvents, DecodeFrames, DecodeHandshake, DropPolicy,
+        Probius, ProtocolFeatures, SinkConfig,
+        sink::test::{WAIT_TIMEOUT, watch::SinkWatch},
+    };
+
+    #[test]
+    fn test_shutdown_and_reinit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let probius = Probius::new();
+        let flusher = ProbiusConfig::new()
+            .app_name("test-app")
+            .app_version("1.2.3")
+            .resource_attribute("env", "test")
+            .sink(SinkConfig::Tcp { addr })
+            .init_instance(&probius)
+            .unwrap();
+        assert_eq!(
+            ProbiusConfig::new().init_instance(&probius).err(),
+            Some(ConfigError::AlreadyInitialized),
+        );
+        let (mut stream, _) = listener.accept().unwrap();
+
+        drop(probius.new_component("before-flush"));
+        flusher.flush();
+        // Sent when the thread exits.
+        std::thread::spawn({
+            let probius = probius.clone();
+            move || drop(probius.new_component("other-thread"))
+        })
+        .join()
+        .unwrap();
+        // Sent by `shutdown`, which returns once the sink thread has exited.
+        drop(probius.new_component("before-shutdown"));
+        probius.shutdown();
+
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+
+        let mut frames = DecodeFrames::new(&received);
+        let handshake = DecodeHandshake::new(frames.next().unwrap()).unwrap();
+        assert_eq!(handshake.session.app_name, "test-app");
+        let resource = &handshake.session.resource;
+        assert_eq!(resource.pid, std::process::id());
+        assert_eq!(resource.app_version, "1.2.3");
+        assert_eq!(resource.probius_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(resource.attribute("env"), Some("test"));
+        assert!(resource.start_time_unix_nanos > 0);
+
+        let names: Vec<_> = frames.by_ref()
+            .flat_map(DecodeEvents::new)
+            .filter_map(|event| match event.body {
+                DecodeEventBody::CreateSource(create_source) => {
+                    Some(create_source.name().unwrap().to_string())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["before-flush", "other-thread", "before-shutdown"]);
+        assert!(frames.remainder().is_empty());
+
+        ProbiusConfig::new().init_instance(&probius).unwrap();
+        probius.shutdown();
+    }
+
+    #[test]
+    fn test_shutdown_with_stalled_collector() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let probius = ProbiusConfig::new()
+            .buffer_count(16)
+            .drop_policy(DropPolicy::Block { timeout: Duration::from_secs(1) })
+            .shutdown_timeout(Duration::from_millis(100))
+            .build_with_sink(TcpSink::new(addr))
+            .unwrap();
+        // The collector accepts the connection but never reads from it.
+        let (_stream, _) = listener.accept().unwrap();
+
+        // Writes only give up once the sink has freed no buffer for a whole second, so it must be
+        // stuck writing to the full socket.
+        let name = "stalled".repeat(100);
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        while probius.backpressure_stats().dropped_events == 0 {
+            assert!(Instant::now() < deadline, "timed out filling the collector's socket");
+            drop(probius.new_component(&name));
+        }
+
+        let start = Instant::now();
+        probius.shutdown();
+        assert!(start.elapsed() < WAIT_TIMEOUT);
+    }
+
+    fn source_names<'a>(frames: impl Iterator<Item = &'a [u8]>) -> Vec<String> {
+        frames
+            .flat_map(DecodeEvents::new)
+            .filter_map(|event| match event.body {
+                DecodeEventBody::CreateSource(create_source) => {
+                    Some(create_source.name().unwrap().to_string())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_spool_replay() {
+        // Find a free port, then leave the collector down.
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let spool_dir =
+            std::env::temp_dir().join(format!("probius-tcp-spool-{}", fastrand::u64(..)));
+        let watch = SinkWatch::default();
+        let probius = ProbiusConfig::new()
+            .app_name("spool-app")
+            .build_with_sink(watch.watch(TcpSink::new(addr).spool(&spool_dir, 1 << 20)))
+            .unwrap();
+
+        let mut created = Vec::new();
+        let mut create = |name: String| {
+            let sent = watch.wait_for(|_| true).sends;
+            drop(probius.new_component(&name));
+            probius.flush();
+            created.push(name);
+            watch.wait_for(|calls| calls.sends > sent);
+        };
+        for i in 0..3 {
+            create(format!("spooled-{i}"));
+        }
+
+        // Once the collector is back, the spool is replayed over its own connection.
+        let listener = TcpListener::bind(addr).unwrap();
+        let replay = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            (listener, received)
+        });
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        let mut i = 0;
+        while !replay.is_finished() {
+            assert!(Instant::now() < deadline, "timed out waiting for the spool to be replayed");
+            create(format!("waiting-{i}"));
+            i += 1;
+        }
+        let (listener, replayed) = replay.join().unwrap();
+
+        let (mut stream, _) = listener.accept().unwrap();
+        create("live".to_string());
+        probius.shutdown();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+
+        let mut replay_frames = DecodeFrames::new(&replayed);
+        let replay_handshake = DecodeHandshake::new(replay_frames.next().unwrap()).unwrap();
+        assert!(replay_handshake.is_replay);
+        let mut live_frames = DecodeFrames::new(&received);
+        let live_handshake = DecodeHandshake::new(live_frames.next().unwrap()).unwrap();
+        assert!(!live_handshake.is_replay);
+        assert_eq!(replay_handshake.session, live_handshake.session);
+
+        // Nothing was lost or reordered.
+        let replayed_names = source_names(replay_frames);
+        assert_eq!(replayed_names[..3], created[..3]);
+        let mut names = replayed_names;
+        names.extend(source_names(live_frames));
+        assert_eq!(names, created);
+        assert_eq!(std::fs::read_dir(&spool_dir).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(&spool_dir).unwrap();
+    }
+
+    fn read_frame(stream: &mut TcpStream) -> Vec<u8> {
+        let mut len = [0u8; 2];
+        stream.read_exact(&mut len).unwrap();
+        let mut frame = vec![0u8; u16::from_le_bytes(len) as usize];
+        stream.read_exact(&mut frame).unwrap();
+        frame
+    }
+
+    #[test]
+    fn test_negotiation() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let sink = TcpSink::new(addr)
+            .compression(FrameCompression::Lz)
+            .negotiate(Duration::from_secs(5));
+        let probius = ProbiusConfig::new()
+            .reconnect_backoff(Duration::from_millis(1), Duration::from_millis(4))
+            .build_with_sink(sink)
+            .unwrap();
+
+        // A collector that can't decode compressed frames turns the session down, and the sink
+        // hangs up.
+        let (mut stream, _) = listener.accept().unwrap();
+        let handshake = DecodeHandshake::new(&read_frame(&mut stream)).unwrap();
+        assert!(handshake.expects_reply);
+        assert_eq!(handshake.features, ProtocolFeatures::COMPRESSION);
+        stream.write_all(&handshake.reply(ProtocolFeatures::NONE)).unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+        assert!(received.is_empty());
+
+        // Once a collector accepts it, the sink sends its buffers.
+        let (mut stream, _) = listener.accept().unwrap();
+        let handshake = DecodeHandshake::new(&read_frame(&mut stream)).unwrap();
+        stream.write_all(&handshake.reply(ProtocolFeatures::SUPPORTED)).unwrap();
+        drop(probius.new_component("negotiated"));
+        probius.flush();
+        probius.shutdown();
+        stream.read_to_end(&mut received).unwrap();
+
+        let mut buf = Vec::new();
+        let mut names = Vec::new();
+        for frame in DecodeFrames::new(&received) {
+            for event in DecodeEvents::new(handshake.decode_frame(frame, &mut buf).unwrap()) {
+                if let DecodeEventBody::CreateSource(create_source) = event.body {
+                    names.push(create_source.name().unwrap().to_string());
+                }
+            }
+        }
+        assert_eq!(names, ["negotiated"]);
+    }
+}
@@ -7,136 +7,464 @@ use core::{
     },
 };
 use std::rc::Rc;
-use std::sync::Mutex;
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::Duration;
 
 use probius_mproto::{GlobalSourceId, MetricAggregate, SourceId};
 
 use crate::{
-    component::{self, Component},
+    BackpressureStats, ClockSource, ConfigError, ProbiusConfig, ProbiusFlusher, SinkHealth,
+    backpressure::Backpressure,
+    clock::SessionClock,
+    component::{self, Component, SourcePath},
     encoding::ProbiusWriter,
+    filter::{self, FilterCache},
     link_vec::{LinkVec, LinkVecPtr},
-    void_sink,
+    sink::{Heartbeat, SinkThread},
 };
 
 static NEXT_SOURCE_ID: AtomicU64 = AtomicU64::new(0);
+static NEXT_INSTANCE_ID: AtomicU64 = AtomicU64::new(0);
 thread_local! {
     static NEXT_EVENT_SEQ: Cell<u16> = Cell::new(0);
-    static PROBIUS: OnceCell<Probius> = OnceCell::new();
+    // This thread's writers, one for each live instance that it has used.
+    static LOCAL_PROBIUS: RefCell<Vec<(Weak<ProbiusShared>, LocalProbius)>> =
+        const { RefCell::new(Vec::new()) };
 }
 
-static APP_CONFIG: Mutex<Option<AppConfig>> = Mutex::new(None);
+static GLOBAL_PROBIUS: LazyLock<Probius> = LazyLock::new(Probius::new);
 
-struct AppConfig {
+pub(crate) struct AppConfig {
     buffer_headroom: usize,
-    buffer_pool: bab::HeapBufferPool,
+    backpressure: Arc<Backpressure>,
+    clock: SessionClock,
+    live_sources: Arc<AtomicU64>,
+    detailed_trace_interval: u32,
+    // Receives the remaining buffers of writers that are dropped, e.g. on thread exit.
+    flusher: Option<ProbiusFlusher>,
+    // Every thread's completed buffers, for `Probius::shutdown` to flush.
+    written_buffers: Mutex<Vec<Weak<Mutex<bab::BufferChain>>>>,
+    sink_thread: Option<SinkThread>,
+    shutdown_timeout: Duration,
 }
 
-pub fn init(buffer_headroom: usize, buffer_pool: bab::HeapBufferPool) {
-    // bab requires that there are at least `num_threads * 3 / 2` batches to avoid any single
-    // thread getting starved. In Probius's case, there will be at most 2 threads - the
-    // publisher and the flusher (which may or may not run on the same thread).
-    //
-    // So we need at least 3 batches, but 4 divides better since people tend to supply large
-    // even numbers for buffer counts.
-    //let batch_count = 4;
-    //let buffers_per_batch = (min_buffer_count + batch_count - 1) / batch_count;
-    //let buffer_pool = bab::HeapBufferPool::new(buffer_size, batch_count, buffers_per_batch);
-
-    let mut app_config = APP_CONFIG.lock().expect("probius app config lock");
-    if app_config.is_some() {
-        drop(app_config);
-        panic!("probius::init called twice");
-    } else {
-        *app_config = Some(AppConfig { buffer_headroom, buffer_pool });
+impl AppConfig {
+    pub(crate) fn new(buffer_headroom: usize, buffer_pool: bab::HeapBufferPool) -> Self {
+        let config = ProbiusConfig::default();
+        Self {
+            buffer_headroom,
+            backpressure: Arc::new(Backpressure::new(config.drop_policy, buffer_pool)),
+            clock: SessionClock::new(config.clock_source, config.clock_anchor_interval),
+            live_sources: Arc::new(AtomicU64::new(0)),
+            detailed_trace_interval: config.detailed_trace_interval,
+            flusher: None,
+            written_buffers: Mutex::default(),
+            sink_thread: None,
+            shutdown_timeout: config.shutdown_timeout,
+        }
     }
-}
 
-fn with_probius<R>(f: impl FnOnce(&Probius) -> R) -> R {
-    try_with_probius(move |probius| {
-        f(probius)
-    })
-}
+    pub(crate) fn from_config(
+        config: &ProbiusConfig,
+        buffer_headroom: usize,
+        buffer_pool: bab::HeapBufferPool,
+        flusher: ProbiusFlusher,
+    ) -> Self {
+        Self {
+            buffer_headroom,
+            backpressure: Arc::new(Backpressure::new(config.drop_policy, buffer_pool)),
+            clock: SessionClock::new(config.clock_source, config.clock_anchor_interval),
+            live_sources: Arc::new(AtomicU64::new(0)),
+            detailed_trace_interval: config.detailed_trace_interval,
+            flusher: Some(flusher),
+            written_buffers: Mutex::default(),
+            sink_thread: None,
+            shutdown_timeout: config.shutdown_timeout,
+        }
+    }
 
-fn try_with_probius<R>(f: impl FnOnce(&Probius) -> R) -> R {
-    PROBIUS.with(move |probius| {
-        let probius = probius.get_or_init(move || {
-            let mut maybe_app_config = APP_CONFIG.lock().expect("probius app config lock");
-            let app_config = maybe_app_config.get_or_insert_with(|| {
-                // Default to the void sink if none was setup by the application.
-                let buffer_pool = bab::HeapBufferPool::new(8192, 16, 16);
-                void_sink::spawn_void_sink_flusher(buffer_pool.clone());
-                AppConfig { buffer_headroom: 0, buffer_pool }
-            });
+    /// The heartbeat sent by this session's sink thread.
+    pub(crate) fn heartbeat(&self, interval: Duration) -> Heartbeat {
+        Heartbeat::new(
+            interval,
+            self.clock.clone(),
+            self.backpressure.clone(),
+            self.live_sources.clone(),
+        )
+    }
 
-            Probius::new(app_config.buffer_headroom, app_config.buffer_pool.clone())
-        });
+    /// Let `Probius::shutdown` flush `writer`'s completed buffers from another thread.
+    fn register_writer(&self, writer: &ProbiusWriter) {
+        let mut written_buffers = self.lock_written_buffers();
+        written_buffers.retain(|buffers| buffers.strong_count() > 0);
+        written_buffers.push(writer.written_buffers());
+    }
 
-        f(probius)
-    })
+    /// Send the buffers that every live writer has completed to the sink.
+    fn flush_writers(&self) {
+        let Some(flusher) = &self.flusher else {
+            return;
+        };
+        for buffers in self.lock_written_buffers().iter().filter_map(Weak::upgrade) {
+            flusher.send(buffers.lock().expect("probius written buffers lock").drain());
+        }
+    }
+
+    fn lock_written_buffers(
+        &self,
+    ) -> std::sync::MutexGuard<'_, Vec<Weak<Mutex<bab::BufferChain>>>> {
+        self.written_buffers.lock().expect("probius writers lock")
+    }
 }
 
+/// Initialize the global instance with a buffer pool whose buffers each reserve `buffer_headroom`
+/// bytes at the start for the sink. Panics if it was already initialized.
+///
+/// Prefer `ProbiusConfig`, which sizes the buffer pool and starts a sink.
+pub fn init(buffer_headroom: usize, buffer_pool: bab::HeapBufferPool) {
+    if let Err(e) = Probius::global().try_init(AppConfig::new(buffer_headroom, buffer_pool)) {
+        panic!("probius::init: {e}");
+    }
+}
+
+/// Shut down the global instance. See `Probius::shutdown`.
+pub fn shutdown() {
+    Probius::global().shutdown();
+}
+
+/// Take the calling thread's completed buffers from the global instance's writer.
 pub fn flush() -> impl Iterator<Item = bab::BufferPtr> {
-    with_probius(|probius| probius.inner.flush())
+    Probius::global().with_local(|probius| probius.inner.writer.flush())
 }
 
 pub fn new_component(name: &str) -> Component {
-    try_with_probius(|probius| {
-        Component::new(probius.clone(), name, false)
-    })
+    Probius::global().new_component(name)
+}
+
+pub fn new_component_ephemeral(name: &str) -> Component {
+    Probius::global().new_component_ephemeral(name)
 }
 
 pub fn enter_component<R>(name: &str, f: impl FnOnce() -> R) -> R {
-    try_with_probius(|probius| {
-        Component::new(probius.clone(), name, false).enter(f)
-    })
+    Probius::global().enter_component(name, f)
 }
 
 pub async fn enter_component_async<F: Future>(name: &str, f: F) -> F::Output {
-    let probius = try_with_probius(|probius| probius.clone());
-
-    Component::new(probius, name, false).enter_async(f).await
+    Probius::global().enter_component_async(name, f).await
 }
 
 pub fn enter_component_ephemeral<R>(name: &str, f: impl FnOnce() -> R) -> R {
-    try_with_probius(|probius| {
-        Component::new(probius.clone(), name, false).enter(f)
-    })
+    Probius::global().enter_component_ephemeral(name, f)
 }
 
 pub async fn enter_component_ephemeral_async<F: Future>(name: &str, f: F) -> F::Output {
-    let probius = try_with_probius(|probius| probius.clone());
-
-    Component::new(probius, name, false).enter_async(f).await
+    Probius::global().enter_component_ephemeral_async(name, f).await
 }
 
 pub fn new_trace_source(name: &str) -> TraceSource {
-    try_with_probius(|probius| {
-        TraceSource::new(probius.clone(), name, true)
-    })
+    Probius::global().new_trace_source(name)
 }
 
 pub fn new_trace_source_ephemeral(name: &str) -> TraceSource {
-    try_with_probius(|probius| {
-        TraceSource::new(probius.clone(), name, false)
-    })
+    Probius::global().new_trace_source_ephemeral(name)
 }
 
+/// A probius pipeline - a buffer pool and a sink, along with each thread's writer into it.
+///
+/// The free functions in this crate use the global instance. Separate instances, created with
+/// `ProbiusConfig::build`, send their sources to their own sink, e.g. so that a library can keep
+/// its telemetry apart from its host's.
 #[derive(Clone)]
 pub struct Probius {
-    inner: Rc<ProbiusWriter>,
+    shared: Arc<ProbiusShared>,
+}
+
+struct ProbiusShared {
+    id: u64,
+    app_config: Mutex<Option<AppConfig>>,
+    // Bumped by `shutdown` so that each thread replaces its writer on next use.
+    generation: AtomicU64,
 }
 
 impl Probius {
-    fn new(
-        buffer_headroom: usize,
-        buffer_pool: bab::HeapBufferPool,
-    ) -> Self {
+    pub(crate) fn new() -> Self {
+        Self {
+            shared: Arc::new(ProbiusShared {
+                id: NEXT_INSTANCE_ID.fetch_add(1, Ordering::Relaxed),
+                app_config: Mutex::new(None),
+                generation: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// The instance used by the free functions in this crate.
+    pub fn global() -> &'static Probius {
+        &GLOBAL_PROBIUS
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.shared.id
+    }
+
+    pub(crate) fn try_init(&self, config: AppConfig) -> Result<(), ConfigError> {
+        let mut app_config = self.lock_app_config();
+        if app_config.is_some() {
+            return Err(ConfigError::AlreadyInitialized);
+        }
+        *app_config = Some(config);
+        Ok(())
+    }
+
+    /// Hand the sink's background thread to `shutdown`, once `try_init` has succeeded.
+    pub(crate) fn set_sink_thread(&self, sink_thread: SinkThread) {
+        if let Some(app_config) = self.lock_app_config().as_mut() {
+            app_config.sink_thread = Some(sink_thread);
+        }
+    }
+
+    /// Tear down this instance: flush every thread's writer, give the sink up to its configured
+    /// shutdown timeout to send everything queued, and stop the sink's thread. Afterwards the
+    /// instance can be initialized again.
+    ///
+    /// Other threads' writers are flushed up to the buffer that each is still filling, which only
+    /// its own thread can complete. Threads that keep running should flush first to send it too.
+    /// Sources created before the call keep writing to the old buffers, which are never sent.
+    pub fn shutdown(&self) {
+        let Some(app_config) = self.lock_app_config().take() else {
+            return;
+        };
+        self.shared.generation.fetch_add(1, Ordering::Relaxed);
+
+        let local = LOCAL_PROBIUS.with_borrow_mut(|local| {
+            let index = local.iter().position(|(_, p)| p.inner.instance_id == self.id())?;
+            Some(local.swap_remove(index))
+        });
+        if let Some((_, local)) = local {
+            local.inner.flush_to_sink();
+        }
+        app_config.flush_writers();
+        if let Some(sink_thread) = app_config.sink_thread {
+            sink_thread.stop(app_config.shutdown_timeout);
+        }
+    }
+
+    /// The state of this instance's sink thread, or `None` if no sink was started.
+    pub fn sink_health(&self) -> Option<SinkHealth> {
+        let app_config = self.lock_app_config();
+        app_config.as_ref()?.sink_thread.as_ref().map(|sink_thread| sink_thread.health())
+    }
+
+    /// What this instance's `DropPolicy` has done since it was initialized.
+    pub fn backpressure_stats(&self) -> BackpressureStats {
+        let app_config = self.lock_app_config();
+        app_config.as_ref().map(|app_config| app_config.backpressure.stats()).unwrap_or_default()
+    }
+
+    /// Where this instance's clock reads timestamps from, which is `ClockSource::Monotonic` if
+    /// the configured source isn't available.
+    pub fn clock_source(&self) -> ClockSource {
+        let app_config = self.lock_app_config();
+        app_config.as_ref().map(|app_config| app_config.clock.source()).unwrap_or_default()
+    }
+
+    /// Send the calling thread's completed buffers to this instance's sink.
+    pub fn flush(&self) {
+        self.with_local(|probius| probius.inner.flush_to_sink());
+    }
+
+    pub fn new_component(&self, name: &str) -> Component {
+        self.with_local(|probius| {
+            Component::new(probius.clone(), name, true)
+        })
+    }
+
+    pub fn new_component_ephemeral(&self, name: &str) -> Component {
+        self.with_local(|probius| {
+            Component::new(probius.clone(), name, false)
+        })
+    }
+
+    pub fn enter_component<R>(&self, name: &str, f: impl FnOnce() -> R) -> R {
+        if !filter::is_enabled() {
+            return f();
+        }
+
+        self.with_local(|probius| {
+            Component::new(probius.clone(), name, true).enter(f)
+        })
+    }
+
+    pub async fn enter_component_async<F: Future>(&self, name: &str, f: F) -> F::Output {
+        if !filter::is_enabled() {
+            return f.await;
+        }
+
+        let probius = self.with_local(|probius| probius.clone());
+
+        Component::new(probius, name, true).enter_async(f).await
+    }
+
+    pub fn enter_component_ephemeral<R>(&self, name: &str, f: impl FnOnce() -> R) -> R {
+        if !filter::is_enabled() {
+            return f();
+        }
+
+        self.with_local(|probius| {
+            Component::new(probius.clone(), name, false).enter(f)
+        })
+    }
+
+    pub async fn enter_component_ephemeral_async<F: Future>(
+        &self,
+        name: &str,
+        f: F,
+    ) -> F::Output {
+        if !filter::is_enabled() {
+            return f.await;
+        }
+
+        let probius = self.with_local(|probius| probius.clone());
+
+        Component::new(probius, name, false).enter_async(f).await
+    }
+
+    pub fn new_trace_source(&self, name: &str) -> TraceSource {
+        self.with_local(|probius| {
+            TraceSource::new(probius.clone(), name, true)
+        })
+    }
+
+    pub fn new_trace_source_ephemeral(&self, name: &str) -> TraceSource {
+        self.with_local(|probius| {
+            TraceSource::new(probius.clone(), name, false)
+        })
+    }
+
+    fn lock_app_config(&self) -> std::sync::MutexGuard<'_, Option<AppConfig>> {
+        self.shared.app_config.lock().expect("probius app config lock")
+    }
+
+    /// Run `f` with the calling thread's writer for this instance, creating it if needed.
+    fn with_local<R>(&self, f: impl FnOnce(&LocalProbius) -> R) -> R {
+        let probius = LOCAL_PROBIUS.with(|local| {
+            let generation = self.shared.generation.load(Ordering::Relaxed);
+            let mut local = local.borrow_mut();
+            let index = local.iter().position(|(_, p)| p.inner.instance_id == self.id());
+            if let Some(index) = index
+                && local[index].1.inner.generation == generation
+            {
+                return local[index].1.clone();
+            }
+
+            let mut maybe_app_config = self.lock_app_config();
+            let app_config = maybe_app_config.get_or_insert_with(|| {
+                // Default to the void sink if none was setup by the application.
+                let config = ProbiusConfig::default();
+                let buffer_pool = config.new_buffer_pool(0);
+                AppConfig::from_config(&config, 0, buffer_pool, ProbiusFlusher::void(self.id()))
+            });
+            let generation = self.shared.generation.load(Ordering::Relaxed);
+            let new_probius = LocalProbius::new(self.id(), app_config, generation);
+            drop(maybe_app_config);
+
+            // The previous writer, if any, is flushed to its sink when dropped here.
+            match index {
+                Some(index) => local[index].1 = new_probius.clone(),
+                None => {
+                    // Writers for instances dropped on other threads are only pruned here.
+                    local.retain(|(shared, _)| shared.strong_count() > 0);
+                    local.push((Arc::downgrade(&self.shared), new_probius.clone()));
+                }
+            }
+            new_probius
+        });
+
+        f(&probius)
+    }
+}
+
+impl Drop for ProbiusShared {
+    fn drop(&mut self) {
+        // Flush and drop the calling thread's writer. This is skipped if the thread is exiting, or
+        // if the last handle was dropped while its writers were borrowed.
+        let _ = LOCAL_PROBIUS.try_with(|local| {
+            if let Ok(mut local) = local.try_borrow_mut() {
+                local.retain(|(shared, _)| shared.strong_count() > 0);
+            }
+        });
+    }
+}
+
+/// Take the calling thread's completed buffers from an instance's writer, if the thread has one.
+pub(crate) fn flush_instance(instance_id: u64) -> impl Iterator<Item = bab::BufferPtr> {
+    LOCAL_PROBIUS.with_borrow(|local| {
+        let probius = local.iter().find(|(_, p)| p.inner.instance_id == instance_id);
+        probius.map(|(_, p)| p.inner.writer.flush()).into_iter().flatten()
+    })
+}
+
+/// A thread's writer into a `Probius` instance.
+#[derive(Clone)]
+pub(crate) struct LocalProbius {
+    inner: Rc<LocalProbiusInner>,
+}
+
+impl LocalProbius {
+    pub(crate) fn new(instance_id: u64, app_config: &AppConfig, generation: u64) -> Self {
+        let writer = ProbiusWriter::new(
+            app_config.buffer_headroom,
+            app_config.backpressure.clone(),
+            app_config.flusher.clone(),
+        );
+        app_config.register_writer(&writer);
         Self {
-            inner: Rc::new(ProbiusWriter::new(buffer_headroom, buffer_pool.clone())),
+            inner: Rc::new(LocalProbiusInner {
+                writer,
+                flusher: app_config.flusher.clone(),
+                clock: app_config.clock.clone(),
+                live_sources: app_config.live_sources.clone(),
+                instance_id,
+                generation,
+                detailed_trace_interval: app_config.detailed_trace_interval,
+            }),
         }
     }
 }
 
+struct LocalProbiusInner {
+    writer: ProbiusWriter,
+    flusher: Option<ProbiusFlusher>,
+    clock: SessionClock,
+    live_sources: Arc<AtomicU64>,
+    instance_id: u64,
+    generation: u64,
+    detailed_trace_interval: u32,
+}
+
+impl LocalProbiusInner {
+    fn flush_to_sink(&self) {
+        if let Some(flusher) = &self.flusher {
+            flusher.send(self.writer.flush());
+        }
+    }
+
+    /// Recalibrate the clock and write a `ClockAnchor` when they're due and no other writer has
+    /// claimed them.
+    #[inline]
+    fn maintain_clock(&self, now_nanos: u64) {
+        self.clock.recalibrate_if_due(now_nanos);
+        if self.clock.claim_anchor(now_nanos) {
+            self.writer.clock_anchor(self.clock.anchor());
+        }
+    }
+}
+
+impl Drop for LocalProbiusInner {
+    fn drop(&mut self) {
+        self.flush_to_sink();
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct EventSeq(pub u16);
 
@@ -180,6 +508,12 @@ pub enum TraceOp {
         to: GlobalSourceId,
         to_version: u64,
     },
+
+    Panicked,
+    Success,
+    Failure { variant: Option<*const str> },
+    Cancelled,
+    FutureStart,
 }
 
 impl TraceOp {
@@ -213,57 +547,54 @@ impl TraceOp {
                     from: *from,
                     to: *to,
                 },
+
+            TraceOp::Panicked => TraceOpAggregate::Panicked,
+            TraceOp::Success => TraceOpAggregate::Success,
+            TraceOp::Failure { variant } => TraceOpAggregate::Failure { variant: *variant },
+            TraceOp::Cancelled => TraceOpAggregate::Cancelled,
+            TraceOp::FutureStart => TraceOpAggregate::FutureStart,
         }
     }
 }
 
 pub struct Source {
-    probius: Probius,
+    probius: LocalProbius,
 
     id: SourceId,
-    #[cfg(not(target_arch = "wasm32"))]
-    create_time: std::time::Instant,
 }
 
 impl Source {
-    pub(crate) fn new(probius: Probius, name: &str, is_recurring: bool) -> Self {
+    pub(crate) fn new(probius: LocalProbius, name: &str, is_recurring: bool) -> Self {
         let source = Self {
             probius: probius.clone(),
 
             id: SourceId { source: NEXT_SOURCE_ID.fetch_add(1, Ordering::Relaxed) },
-            #[cfg(not(target_arch = "wasm32"))]
-            create_time: std::time::Instant::now(),
         };
+        probius.inner.live_sources.fetch_add(1, Ordering::Relaxed);
 
-        component::with_current(|parent| {
-            probius.inner.create_source(
-                source.next_event_id(),
-                name,
-                parent.map(|p| p.id()),
-                is_recurring,
-            );
-        });
+        probius.inner.writer.create_source(
+            source.next_event_id(),
+            name,
+            component::current_id(),
+            is_recurring,
+        );
 
         source
     }
 
     pub fn id(&self) -> SourceId { self.id }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    fn now_nanos(&self) -> u64 {
-        self.create_time.elapsed().as_nanos() as u64
-    }
-
-    #[cfg(target_arch = "wasm32")]
+    /// Nanoseconds since the session started, on the clock shared by all of its sources.
+    #[inline]
     fn now_nanos(&self) -> u64 {
-        // TODO
-        0
+        self.probius.inner.clock.now_nanos()
     }
 
     fn next_event_id(&self) -> probius_mproto::EventId {
         let seq = NEXT_EVENT_SEQ.get();
         NEXT_EVENT_SEQ.set(seq.wrapping_add(1));
         let timestamp_nanos = self.now_nanos();
+        self.probius.inner.maintain_clock(timestamp_nanos);
         probius_mproto::EventId {
             source: self.id,
             timestamp_nanos,
@@ -274,7 +605,8 @@ impl Source {
 
 impl Drop for Source {
     fn drop(&mut self) {
-        self.probius.inner.delete_source(self.next_event_id());
+        self.probius.inner.live_sources.fetch_sub(1, Ordering::Relaxed);
+        self.probius.inner.writer.delete_source(self.next_event_id());
     }
 }
 
@@ -282,59 +614,144 @@ thread_local! {
     static TRACE_STACK: Cell<Option<NonNull<()>>> = Cell::new(None);
 }
 
+/// By default a re-entrant trace is recorded as a single `Call` to its own source, with everything
+/// below it collapsed into that node.
+const DEFAULT_MAX_REENTRY_DEPTH: u16 = 0;
+
 pub struct TraceSource {
     source: Source,
     trace_aggregator: TraceAggregator,
+    max_reentry_depth: u16,
+    // Path used to match this source against the process-wide filter.
+    path: SourcePath,
+    filter_cache: FilterCache,
+    // Number of traces started, for sampling detailed traces.
+    trace_count: Cell<u32>,
 }
 
 impl TraceSource {
-    fn new(probius: Probius, name: &str, is_recurring: bool) -> Self {
+    fn new(probius: LocalProbius, name: &str, is_recurring: bool) -> Self {
         Self {
             source: Source::new(probius, name, is_recurring),
             trace_aggregator: TraceAggregator::new(),
+            max_reentry_depth: DEFAULT_MAX_REENTRY_DEPTH,
+            path: SourcePath::new(name),
+            filter_cache: FilterCache::new(),
+            trace_count: Cell::new(0),
         }
     }
 
+    /// Whether traces from this source are currently recorded, according to the runtime switch
+    /// and filter.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.filter_cache.is_enabled(&self.path)
+    }
+
+    /// Set how many levels of recursion (or re-entry from a nested future) are recorded inline in
+    /// this source's aggregate graph. Each re-entry is recorded as a `Call` to this source followed
+    /// by the nested operations in their own scope. Re-entries deeper than `depth` are collapsed
+    /// into the `Call` node at the deepest recorded level.
+    pub fn with_max_reentry_depth(mut self, depth: u16) -> Self {
+        self.max_reentry_depth = depth;
+        self
+    }
+
     #[inline]
     pub fn trace<R>(&self, f: impl FnOnce() -> R) -> R {
-        let trace = Trace {
-            is_detailed_trace: false,
-            start_nanos: self.source.now_nanos(),
-            trace_source: &self,
-            aggregate_cursor: TraceAggregateCursor::start_cursor(),
-            encode_cursor: Cell::new(0),
-            encode_buf: UnsafeCell::new([0; 512]),
-        };
-        let parent = TRACE_STACK.replace(Some(NonNull::from(&trace).cast()));
+        self.trace_with_outcome(f, |_, _| { })
+    }
 
-        let result = f();
+    #[inline]
+    pub async fn trace_future<R>(&self, f: impl core::future::Future<Output = R>) -> R {
+        self.trace_future_with_outcome(f, |_, _| { }).await
+    }
 
-        TRACE_STACK.set(parent);
+    /// Like `trace`, but also record whether `f` succeeded or failed so that success and error
+    /// counts are aggregated for each path through the graph.
+    #[inline]
+    pub fn trace_result<T, E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+        self.trace_with_outcome(f, |trace, result| trace.outcome(result, |_| None))
+    }
+
+    /// Like `trace_result`, but errors are further split by the variant name returned by
+    /// `variant`.
+    #[inline]
+    pub fn trace_result_tagged<T, E>(
+        &self,
+        f: impl FnOnce() -> Result<T, E>,
+        variant: impl FnOnce(&E) -> &'static str,
+    ) -> Result<T, E> {
+        self.trace_with_outcome(f, |trace, result| {
+            trace.outcome(result, |e| Some(variant(e)))
+        })
+    }
+
+    /// Like `trace_future`, but also record whether the future resolved to `Ok` or `Err`.
+    #[inline]
+    pub async fn trace_future_result<T, E>(
+        &self,
+        f: impl core::future::Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        self.trace_future_with_outcome(f, |trace, result| trace.outcome(result, |_| None)).await
+    }
+
+    /// Like `trace_future_result`, but errors are further split by the variant name returned by
+    /// `variant`.
+    #[inline]
+    pub async fn trace_future_result_tagged<T, E>(
+        &self,
+        f: impl core::future::Future<Output = Result<T, E>>,
+        variant: impl FnOnce(&E) -> &'static str,
+    ) -> Result<T, E> {
+        self.trace_future_with_outcome(f, |trace, result| {
+            trace.outcome(result, |e| Some(variant(e)))
+        })
+        .await
+    }
+
+    #[inline]
+    fn trace_with_outcome<R>(
+        &self,
+        f: impl FnOnce() -> R,
+        outcome: impl FnOnce(&Trace, &R),
+    ) -> R {
+        if !self.is_enabled() {
+            return f();
+        }
+
+        let trace = Trace::new(self);
+
+        let result = trace.enter(f);
+
+        outcome(&trace, &result);
+        trace.end();
         drop(trace);
 
         result
     }
 
     #[inline]
-    pub async fn trace_future<R>(&self, f: impl core::future::Future<Output = R>) -> R {
-        let trace = Trace {
-            is_detailed_trace: false,
-            start_nanos: self.source.now_nanos(),
-            trace_source: self,
-            aggregate_cursor: TraceAggregateCursor::start_cursor(),
-            encode_cursor: Cell::new(0),
-            encode_buf: UnsafeCell::new([0; 512]),
-        };
+    async fn trace_future_with_outcome<R>(
+        &self,
+        f: impl core::future::Future<Output = R>,
+        outcome: impl FnOnce(&Trace, &R),
+    ) -> R {
+        if !self.is_enabled() {
+            return f.await;
+        }
+
+        let trace = Trace::new(self);
+        trace.start_future();
 
         let mut f = core::pin::pin!(f);
         let result = core::future::poll_fn(|cx| {
-            let parent = TRACE_STACK.replace(Some(NonNull::from(&trace).cast()));
-            let result = f.as_mut().poll(cx);
-            TRACE_STACK.set(parent);
-            result
+            trace.poll(|| f.as_mut().poll(cx))
         })
         .await;
 
+        outcome(&trace, &result);
+        trace.end();
         drop(trace);
 
         result
@@ -343,6 +760,19 @@ impl TraceSource {
     pub fn flush_aggregate_full(&self) {
         self.trace_aggregator.flush_full(&self.source);
     }
+
+    /// Whether the next trace should be recorded in detail, per the configured sampling interval.
+    #[inline]
+    fn sample_detailed_trace(&self) -> bool {
+        let interval = self.source.probius.inner.detailed_trace_interval;
+        if interval == 0 {
+            return false;
+        }
+
+        let count = self.trace_count.get();
+        self.trace_count.set(count.wrapping_add(1));
+        count.is_multiple_of(interval)
+    }
 }
 
 #[inline]
@@ -353,6 +783,20 @@ fn with_current_trace(f: impl FnOnce(&Trace)) {
     }
 }
 
+/// Find the innermost active trace started by `trace_source`, if any.
+#[inline]
+fn with_active_trace_from(trace_source: &TraceSource, f: impl FnOnce(&Trace)) {
+    let mut next = TRACE_STACK.get();
+    while let Some(trace_ptr) = next {
+        let trace: &Trace = unsafe { trace_ptr.cast().as_ref() };
+        if core::ptr::eq(trace.trace_source, trace_source) {
+            f(trace);
+            return;
+        }
+        next = trace.parent.get();
+    }
+}
+
 /*pub fn trace_create_source(name: &str) -> Source {
     let source = Source::new(name);
     with_current_trace(|trace| {
@@ -399,12 +843,179 @@ pub struct Trace<'a> {
     is_detailed_trace: bool,
     start_nanos: u64,
     trace_source: &'a TraceSource,
+    // The trace that was on top of TRACE_STACK when this trace was last entered.
+    parent: Cell<Option<NonNull<()>>>,
+    // How many traces from the same source enclose this one.
+    reentry_depth: u16,
+    // Set once the traced function has returned or panicked. A trace dropped before then belongs
+    // to a future that was cancelled.
+    is_ended: Cell<bool>,
+    poll_stats: PollStats,
     aggregate_cursor: TraceAggregateCursor,
     encode_cursor: Cell<usize>,
     encode_buf: UnsafeCell<[u8; 512]>,
 }
 
-impl Trace<'_> {
+impl<'a> Trace<'a> {
+    #[inline]
+    fn new(trace_source: &'a TraceSource) -> Self {
+        let mut trace = Trace {
+            is_detailed_trace: trace_source.sample_detailed_trace(),
+            start_nanos: trace_source.source.now_nanos(),
+            trace_source,
+            parent: Cell::new(None),
+            reentry_depth: 0,
+            is_ended: Cell::new(false),
+            poll_stats: PollStats::default(),
+            aggregate_cursor: TraceAggregateCursor::start_cursor(),
+            encode_cursor: Cell::new(0),
+            encode_buf: UnsafeCell::new([0; 512]),
+        };
+
+        // Re-entering a source continues the enclosing trace's path in the aggregate graph rather
+        // than starting a second, overlapping path from the start node.
+        with_active_trace_from(trace_source, |outer| {
+            outer.push_op(TraceOp::Call { source: trace_source.source.id });
+            trace.reentry_depth = outer.reentry_depth.saturating_add(1);
+            trace.aggregate_cursor = outer.aggregate_cursor.fork();
+        });
+        if trace.reentry_depth > 0 {
+            trace.push_op(TraceOp::PushScope);
+        }
+
+        trace
+    }
+
+    /// Run `f` with this trace on top of TRACE_STACK. If `f` panics, the trace is removed from
+    /// TRACE_STACK and the panic is recorded before unwinding continues.
+    #[inline]
+    fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.parent.set(TRACE_STACK.replace(Some(NonNull::from(self).cast())));
+        let guard = TraceEnterGuard { trace: self };
+        let result = f();
+        // The guard is only dropped by a panic unwinding out of `f`.
+        core::mem::forget(guard);
+        TRACE_STACK.set(self.parent.get());
+        result
+    }
+
+    /// Mark this trace as tracing a future. Its poll statistics are recorded as metrics on a
+    /// `FutureStart` node at the start of the trace.
+    #[inline]
+    fn start_future(&self) {
+        if self.is_collapsed() {
+            return;
+        }
+        self.push_op(TraceOp::FutureStart);
+        self.poll_stats.start_node.set(self.aggregate_cursor.node.get());
+    }
+
+    /// Poll a traced future via `f`, accumulating the time spent inside and between polls.
+    #[inline]
+    fn poll<R>(&self, f: impl FnOnce() -> R) -> R {
+        let stats = &self.poll_stats;
+        let poll_start_nanos = self.trace_source.source.now_nanos();
+        if let Some(last_poll_end_nanos) = stats.last_poll_end_nanos.get() {
+            stats.idle_nanos.set(
+                stats.idle_nanos.get() + poll_start_nanos.saturating_sub(last_poll_end_nanos),
+            );
+        }
+
+        let result = self.enter(f);
+
+        let poll_end_nanos = self.trace_source.source.now_nanos();
+        stats.polls.set(stats.polls.get() + 1);
+        stats.busy_nanos.set(
+            stats.busy_nanos.get() + poll_end_nanos.saturating_sub(poll_start_nanos),
+        );
+        stats.last_poll_end_nanos.set(Some(poll_end_nanos));
+
+        result
+    }
+
+    #[inline]
+    fn record_poll_stats(&self) {
+        let stats = &self.poll_stats;
+        let Some(start_node) = stats.start_node.get() else {
+            return;
+        };
+        let TraceAggregateNodeData::FutureStart { polls, busy_nanos, idle_nanos } = start_node.op
+        else {
+            return;
+        };
+
+        let aggregator = &self.trace_source.trace_aggregator;
+        aggregator.record_metric(polls, stats.polls.get() as i64);
+        aggregator.record_metric(busy_nanos, stats.busy_nanos.get() as i64);
+        aggregator.record_metric(idle_nanos, stats.idle_nanos.get() as i64);
+    }
+
+    /// Called once the traced function has returned and this trace has been removed from
+    /// TRACE_STACK.
+    #[inline]
+    fn end(&self) {
+        self.is_ended.set(true);
+        self.record_poll_stats();
+        if self.reentry_depth > 0 {
+            self.push_op(TraceOp::PopScope);
+            self.join_outer();
+        }
+    }
+
+    /// Called when a panic unwinds out of the traced function, after this trace has been removed
+    /// from TRACE_STACK.
+    #[cold]
+    fn end_panicked(&self) {
+        self.is_ended.set(true);
+        self.record_poll_stats();
+        // A panic unwinding out of nested re-entrant traces is recorded once, by the innermost one.
+        let is_recorded = self.aggregate_cursor.node.get()
+            .is_some_and(|node| matches!(node.op, TraceAggregateNodeData::Panicked { .. }));
+        if !is_recorded {
+            self.push_op(TraceOp::Panicked);
+        }
+        if self.reentry_depth > 0 {
+            self.join_outer();
+        }
+    }
+
+    /// Called when a traced future is dropped before it completes.
+    #[cold]
+    fn end_cancelled(&self) {
+        self.is_ended.set(true);
+        self.record_poll_stats();
+        self.push_op(TraceOp::Cancelled);
+        if self.reentry_depth > 0 {
+            self.join_outer();
+        }
+    }
+
+    #[inline]
+    fn join_outer(&self) {
+        with_active_trace_from(self.trace_source, |outer| {
+            outer.aggregate_cursor.join(&self.aggregate_cursor);
+        });
+    }
+
+    #[inline]
+    fn outcome<T, E>(
+        &self,
+        result: &Result<T, E>,
+        variant: impl FnOnce(&E) -> Option<&'static str>,
+    ) {
+        match result {
+            Ok(_) => self.push_op(TraceOp::Success),
+            Err(e) => self.push_op(TraceOp::Failure {
+                variant: variant(e).map(|v| v as *const str),
+            }),
+        }
+    }
+
+    #[inline]
+    fn is_collapsed(&self) -> bool {
+        self.reentry_depth > self.trace_source.max_reentry_depth
+    }
+
     /*fn create_source(&self, probius: Probius, name: &'static str, is_recurring: bool) -> Source {
         let source = Source::new(probius, name, is_recurring);
         self.push_op(TraceOp::CreateSource { source: source.id });
@@ -433,6 +1044,10 @@ impl Trace<'_> {
 
     #[inline]
     fn push_op(&self, op: TraceOp) {
+        if self.is_collapsed() {
+            return;
+        }
+
         let op_node_index = self.trace_source.trace_aggregator.ingest(&self.aggregate_cursor, &op);
 
         if self.is_detailed_trace {
@@ -479,6 +1094,12 @@ impl Trace<'_> {
             }
             TraceOp::GlobalChannelTransferFrom { .. } => {
             }
+
+            TraceOp::Panicked => { }
+            TraceOp::Success => { }
+            TraceOp::Failure { .. } => { }
+            TraceOp::Cancelled => { }
+            TraceOp::FutureStart => { }
         }
 
         Ok(())
@@ -505,11 +1126,36 @@ impl Trace<'_> {
     }
 }
 
+#[derive(Default)]
+struct PollStats {
+    start_node: Cell<Option<TraceAggregateNodePtr>>,
+    polls: Cell<u64>,
+    busy_nanos: Cell<u64>,
+    idle_nanos: Cell<u64>,
+    last_poll_end_nanos: Cell<Option<u64>>,
+}
+
+struct TraceEnterGuard<'t, 'a> {
+    trace: &'t Trace<'a>,
+}
+
+impl Drop for TraceEnterGuard<'_, '_> {
+    #[inline]
+    fn drop(&mut self) {
+        TRACE_STACK.set(self.trace.parent.get());
+        self.trace.end_panicked();
+    }
+}
+
 impl Drop for Trace<'_> {
     fn drop(&mut self) {
+        if !self.is_ended.get() {
+            self.end_cancelled();
+        }
+
         if self.is_detailed_trace {
             let encode_buf = unsafe { &*self.encode_buf.get() };
-            self.trace_source.source.probius.inner.trace(
+            self.trace_source.source.probius.inner.writer.trace(
                 self.trace_source.source.next_event_id(),
                 self.start_nanos,
                 &encode_buf[..self.encode_cursor.get()],
@@ -544,6 +1190,12 @@ pub enum TraceOpAggregate {
         from: GlobalSourceId,
         to: GlobalSourceId,
     },
+
+    Panicked,
+    Success,
+    Failure { variant: Option<*const str> },
+    Cancelled,
+    FutureStart,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -579,6 +1231,22 @@ pub enum TraceAggregateNodeData {
         from: GlobalSourceId,
         to: GlobalSourceId,
     },
+
+    Panicked { index: u16 },
+    Success { index: u16 },
+    Failure {
+        variant: Option<&'static str>,
+        index: u16,
+    },
+    Cancelled { index: u16 },
+    /// The first node of every traced future. Each field is the index of a metric aggregating,
+    /// per trace, the number of polls, the time spent inside `poll` and the time spent waiting
+    /// between polls.
+    FutureStart {
+        polls: u16,
+        busy_nanos: u16,
+        idle_nanos: u16,
+    },
 }
 
 impl TraceAggregateNodeData {
@@ -614,6 +1282,13 @@ impl TraceAggregateNodeData {
                     from: *from,
                     to: *to,
                 },
+
+            TraceAggregateNodeData::Panicked { .. } => TraceOpAggregate::Panicked,
+            TraceAggregateNodeData::Success { .. } => TraceOpAggregate::Success,
+            TraceAggregateNodeData::Failure { variant, .. } =>
+                TraceOpAggregate::Failure { variant: variant.map(|v| v as *const str) },
+            TraceAggregateNodeData::Cancelled { .. } => TraceOpAggregate::Cancelled,
+            TraceAggregateNodeData::FutureStart { .. } => TraceOpAggregate::FutureStart,
         }
     }
 
@@ -659,6 +1334,20 @@ impl TraceAggregateNodeData {
                 probius_mproto::TraceOpAggregate::GlobalChannelReceive { channel },
             TraceAggregateNodeData::GlobalChannelTransferFrom { from, to } =>
                 probius_mproto::TraceOpAggregate::GlobalChannelTransfer { from, to },
+
+            TraceAggregateNodeData::Panicked { index } =>
+                probius_mproto::TraceOpAggregate::Panicked { index },
+            TraceAggregateNodeData::Success { index } =>
+                probius_mproto::TraceOpAggregate::Success { index },
+            TraceAggregateNodeData::Failure { variant, index } =>
+                probius_mproto::TraceOpAggregate::Failure {
+                    variant: variant.map(Into::into),
+                    index,
+                },
+            TraceAggregateNodeData::Cancelled { index } =>
+                probius_mproto::TraceOpAggregate::Cancelled { index },
+            TraceAggregateNodeData::FutureStart { polls, busy_nanos, idle_nanos } =>
+                probius_mproto::TraceOpAggregate::FutureStart { polls, busy_nanos, idle_nanos },
         }
     }
 }
@@ -689,6 +1378,22 @@ impl TraceAggregateCursor {
             branch_end: Cell::new(None),
         }
     }
+
+    /// Start a new cursor at this cursor's current position.
+    #[inline]
+    fn fork(&self) -> Self {
+        Self {
+            node: Cell::new(self.node.get()),
+            branch_end: Cell::new(self.branch_end.get()),
+        }
+    }
+
+    /// Move this cursor to the position of a cursor previously forked from it.
+    #[inline]
+    fn join(&self, forked: &Self) {
+        self.node.set(forked.node.get());
+        self.branch_end.set(forked.branch_end.get());
+    }
 }
 
 type TraceAggregateNodePtr = LinkVecPtr<TraceAggregateNode>;
@@ -696,6 +1401,7 @@ type TraceAggregateNodePtr = LinkVecPtr<TraceAggregateNode>;
 /// Aggregator for traces from a single TraceSource
 pub struct TraceAggregator {
     start_node: OnceCell<TraceAggregateNodePtr>,
+    counters: RefCell<Vec<u32>>,
     metrics: RefCell<Vec<MetricAggregate>>,
     nodes: LinkVec<TraceAggregateNode>,
 }
@@ -704,6 +1410,7 @@ impl TraceAggregator {
     fn new() -> Self {
         Self {
             start_node: OnceCell::new(),
+            counters: RefCell::new(Vec::new()),
             metrics: RefCell::new(Vec::new()),
             nodes: LinkVec::leak(),
         }
@@ -756,14 +1463,15 @@ impl TraceAggregator {
             }
             TraceAggregateNodeData::Metric { index, .. } => {
                 if let TraceOp::Metric { value, .. } = op {
-                    if let Some(metric_aggregate) =
-                        self.metrics.borrow_mut().get_mut(*index as usize)
-                    {
-                        metric_aggregate.count += 1;
-                        metric_aggregate.sum += *value;
-                        metric_aggregate.min = core::cmp::min(metric_aggregate.min, *value);
-                        metric_aggregate.max = core::cmp::max(metric_aggregate.max, *value);
-                    }
+                    self.record_metric(*index, *value);
+                }
+            }
+            TraceAggregateNodeData::Panicked { index }
+            | TraceAggregateNodeData::Success { index }
+            | TraceAggregateNodeData::Failure { index, .. }
+            | TraceAggregateNodeData::Cancelled { index } => {
+                if let Some(counter) = self.counters.borrow_mut().get_mut(*index as usize) {
+                    *counter = counter.saturating_add(1);
                 }
             }
             _ => { }
@@ -774,6 +1482,23 @@ impl TraceAggregator {
         node.index
     }
 
+    #[inline]
+    fn record_metric(&self, index: u16, value: i64) {
+        if let Some(metric_aggregate) = self.metrics.borrow_mut().get_mut(index as usize) {
+            metric_aggregate.count += 1;
+            metric_aggregate.sum += value;
+            metric_aggregate.min = core::cmp::min(metric_aggregate.min, value);
+            metric_aggregate.max = core::cmp::max(metric_aggregate.max, value);
+        }
+    }
+
+    fn new_counter(&self) -> u16 {
+        let mut counters = self.counters.borrow_mut();
+        let index = counters.len() as u16;
+        counters.push(0);
+        index
+    }
+
     fn new_metric(&self) -> u16 {
         let mut metrics = self.metrics.borrow_mut();
         let index = metrics.len() as u16;
@@ -838,6 +1563,19 @@ impl TraceAggregator {
                     from: *from,
                     to: *to,
                 },
+
+            TraceOp::Panicked => TraceAggregateNodeData::Panicked { index: self.new_counter() },
+            TraceOp::Success => TraceAggregateNodeData::Success { index: self.new_counter() },
+            TraceOp::Failure { variant } => TraceAggregateNodeData::Failure {
+                variant: variant.map(|v| unsafe { &*v }),
+                index: self.new_counter(),
+            },
+            TraceOp::Cancelled => TraceAggregateNodeData::Cancelled { index: self.new_counter() },
+            TraceOp::FutureStart => TraceAggregateNodeData::FutureStart {
+                polls: self.new_metric(),
+                busy_nanos: self.new_metric(),
+                idle_nanos: self.new_metric(),
+            },
         };
 
         self.nodes.push(TraceAggregateNode {
@@ -849,12 +1587,13 @@ impl TraceAggregator {
     }
 
     fn flush_full(&self, source: &Source) {
+        let mut counters = self.counters.borrow_mut();
         let mut metrics = self.metrics.borrow_mut();
 
-        source.probius.inner.trace_aggregate(
+        source.probius.inner.writer.trace_aggregate(
             source.next_event_id(),
             source.now_nanos(), // TODO this should be the previous flush time, not now
-            &[],
+            &counters[..],
             &metrics[..],
             self.nodes.iter().map(|n| {
                 probius_mproto::TraceAggregateNodeGen {
@@ -865,17 +1604,13 @@ impl TraceAggregator {
             }),
         );
 
-        for node in self.nodes.iter() {
-            match node.op {
-                TraceAggregateNodeData::Metric { index, .. } => {
-                    let index = index as usize;
-                    metrics[index].count = 0;
-                    metrics[index].sum = 0;
-                    metrics[index].min = i64::MAX;
-                    metrics[index].max = i64::MIN;
-                }
-                _ => {}
-            }
+        // Every counter and metric belongs to exactly one node, so they can be reset wholesale.
+        counters.fill(0);
+        for metric in metrics.iter_mut() {
+            metric.count = 0;
+            metric.sum = 0;
+            metric.min = i64::MAX;
+            metric.max = i64::MIN;
         }
     }
 
@@ -975,7 +1710,7 @@ impl Drop for TraceAggregator {
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
 
     #[test]
@@ -1023,4 +1758,365 @@ mod test {
             println!("Flushed: {:?}", unsafe { flushed_buffer.slice(0..len) });
         }
     }
+
+    #[test]
+    fn test_separate_instances() {
+        fn created_sources(probius: &Probius) -> Vec<String> {
+            let mut names = Vec::new();
+            for buffer in flush_instance(probius.id()) {
+                let len = bab::WriterFlushSender::get_complete_buffer_len(buffer) as usize;
+                for event in crate::DecodeEvents::new(unsafe { buffer.slice(0..len) }) {
+                    if let crate::DecodeEventBody::CreateSource(create_source) = event.body {
+                        names.push(create_source.name().unwrap().to_string());
+                    }
+                }
+                unsafe { buffer.release(); }
+            }
+            names
+        }
+
+        let a = ProbiusConfig::new().build().unwrap();
+        let b = ProbiusConfig::new().build().unwrap();
+        let _a = a.new_component("a");
+        let _b = b.new_trace_source("b");
+        assert_eq!(created_sources(&a), ["a"]);
+        assert_eq!(created_sources(&b), ["b"]);
+    }
+
+    #[test]
+    fn test_dropped_instance_writers() {
+        fn has_writer(instance_id: u64) -> bool {
+            LOCAL_PROBIUS.with_borrow(|local| {
+                local.iter().any(|(_, p)| p.inner.instance_id == instance_id)
+            })
+        }
+
+        let a = ProbiusConfig::new().build().unwrap();
+        drop(a.new_component("a"));
+        assert!(has_writer(a.id()));
+        let a_id = a.id();
+        drop(a);
+        assert!(!has_writer(a_id));
+
+        // A writer for an instance dropped on another thread is pruned when this thread next
+        // creates one.
+        let b = ProbiusConfig::new().build().unwrap();
+        drop(b.new_component("b"));
+        let b_id = b.id();
+        std::thread::spawn(move || drop(b)).join().unwrap();
+        assert!(has_writer(b_id));
+        let c = ProbiusConfig::new().build().unwrap();
+        drop(c.new_component("c"));
+        assert!(!has_writer(b_id));
+        assert!(has_writer(c.id()));
+    }
+
+    #[test]
+    fn test_session_clock() {
+        let probius = ProbiusConfig::new()
+            .clock_anchor_interval(Duration::from_secs(3600))
+            .build()
+            .unwrap();
+        let _a = probius.new_trace_source("a");
+        std::thread::sleep(Duration::from_millis(10));
+        let _b = probius.new_trace_source("b");
+
+        let mut anchors = Vec::new();
+        let mut created = Vec::new();
+        for buffer in flush_instance(probius.id()) {
+            let len = bab::WriterFlushSender::get_complete_buffer_len(buffer) as usize;
+            for event in crate::DecodeEvents::new(unsafe { buffer.slice(0..len) }) {
+                match event.body {
+                    crate::DecodeEventBody::ClockAnchor(anchor) => {
+                        assert_eq!(event.id.source, crate::SESSION_SOURCE);
+                        anchors.push(anchor);
+                    }
+                    crate::DecodeEventBody::CreateSource(_) => {
+                        created.push(event.id.timestamp_nanos);
+                    }
+                    _ => {}
+                }
+            }
+            unsafe { buffer.release(); }
+        }
+
+        // Both sources are timed from the start of the session, not from their own creation.
+        assert_eq!(created.len(), 2);
+        assert!(created[1] - created[0] >= 10_000_000);
+        // The first event of the session brought along the only anchor due within the hour.
+        assert_eq!(anchors.len(), 1);
+        assert!(anchors[0].monotonic_nanos >= created[0]);
+        assert!(anchors[0].unix_nanos > 0);
+    }
+
+    pub(crate) fn test_probius() -> LocalProbius {
+        LocalProbius::new(u64::MAX, &AppConfig::new(0, bab::HeapBufferPool::new(8192, 4, 16)), 0)
+    }
+
+    fn aggregate_ops(tracer: &TraceSource) -> Vec<TraceOpAggregate> {
+        tracer.trace_aggregator.nodes.iter().map(|n| n.op.as_op_aggregate()).collect()
+    }
+
+    // Labels are compared by address, so share a single copy of each.
+    static ENTER: &str = "enter";
+    static EXIT: &str = "exit";
+
+    fn recurse(tracer: &TraceSource, depth: usize) {
+        tracer.trace(|| {
+            trace_label(ENTER);
+            if depth > 0 {
+                recurse(tracer, depth - 1);
+            }
+            trace_label(EXIT);
+        });
+    }
+
+    #[test]
+    fn test_trace_panic() {
+        let probius = test_probius();
+        let tracer = TraceSource::new(probius, "test-panic", true);
+
+        for i in 0..3 {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                tracer.trace(|| {
+                    trace_label(ENTER);
+                    if i > 0 {
+                        panic!("test panic");
+                    }
+                    trace_label(EXIT);
+                })
+            }));
+            assert_eq!(result.is_err(), i > 0);
+            assert!(TRACE_STACK.get().is_none());
+            assert!(component::current_id().is_none());
+        }
+
+        // Tracing still works after a panic and the panic is counted on its own terminal node.
+        trace_label(EXIT);
+        tracer.trace(|| {
+            trace_label(ENTER);
+            trace_label(EXIT);
+        });
+        let enter: *const str = ENTER;
+        let exit: *const str = EXIT;
+        assert_eq!(
+            aggregate_ops(&tracer),
+            vec![
+                TraceOpAggregate::Label { label: enter },
+                TraceOpAggregate::Label { label: exit },
+                TraceOpAggregate::Panicked,
+            ],
+        );
+        assert_eq!(&tracer.trace_aggregator.counters.borrow()[..], &[2]);
+    }
+
+    #[test]
+    fn test_trace_during_unwind() {
+        struct TraceOnDrop<'a>(&'a TraceSource);
+
+        impl Drop for TraceOnDrop<'_> {
+            fn drop(&mut self) {
+                self.0.trace(|| trace_label(ENTER));
+            }
+        }
+
+        let probius = test_probius();
+        let tracer = TraceSource::new(probius, "test-unwind", true);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _trace_on_drop = TraceOnDrop(&tracer);
+            panic!("test panic");
+        }));
+        assert!(result.is_err());
+
+        // A trace that completes while some other panic unwinds is not a panicked trace.
+        let enter: *const str = ENTER;
+        assert_eq!(aggregate_ops(&tracer), vec![TraceOpAggregate::Label { label: enter }]);
+        assert!(tracer.trace_aggregator.counters.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_trace_result() {
+        let probius = test_probius();
+        let tracer = TraceSource::new(probius, "test-result", true);
+
+        static NOT_FOUND: &str = "NotFound";
+        for i in 0..6 {
+            let result = tracer.trace_result_tagged(
+                || {
+                    trace_label(ENTER);
+                    if i % 3 == 0 { Err(i) } else { Ok(i) }
+                },
+                |_| NOT_FOUND,
+            );
+            assert_eq!(result.is_err(), i % 3 == 0);
+        }
+        let _ = pollster::block_on(tracer.trace_future_result(async {
+            trace_label(ENTER);
+            Err::<(), ()>(())
+        }));
+
+        let enter: *const str = ENTER;
+        let not_found: *const str = NOT_FOUND;
+        assert_eq!(
+            aggregate_ops(&tracer),
+            vec![
+                TraceOpAggregate::Label { label: enter },
+                TraceOpAggregate::Failure { variant: Some(not_found) },
+                TraceOpAggregate::Success,
+                TraceOpAggregate::FutureStart,
+                TraceOpAggregate::Label { label: enter },
+                TraceOpAggregate::Failure { variant: None },
+            ],
+        );
+        assert_eq!(&tracer.trace_aggregator.counters.borrow()[..], &[2, 4, 1]);
+    }
+
+    #[test]
+    fn test_detailed_trace_sampling() {
+        let mut app_config = AppConfig::new(0, bab::HeapBufferPool::new(8192, 4, 16));
+        app_config.detailed_trace_interval = 3;
+        let tracer = TraceSource::new(LocalProbius::new(0, &app_config, 0), "test-sampling", true);
+
+        for _ in 0..7 {
+            tracer.trace(|| trace_label(ENTER));
+        }
+
+        let mut detailed_traces = 0;
+        for buffer in tracer.source.probius.inner.writer.flush() {
+            let len = bab::WriterFlushSender::get_complete_buffer_len(buffer) as usize;
+            for event in crate::DecodeEvents::new(unsafe { buffer.slice(0..len) }) {
+                if let crate::DecodeEventBody::Trace { .. } = event.body {
+                    detailed_traces += 1;
+                }
+            }
+            unsafe { buffer.release(); }
+        }
+        assert_eq!(detailed_traces, 3);
+    }
+
+    #[test]
+    fn test_trace_future_cancelled() {
+        let probius = test_probius();
+        let tracer = TraceSource::new(probius, "test-cancelled", true);
+
+        let waker = std::task::Waker::noop();
+        let mut cx = core::task::Context::from_waker(waker);
+        for i in 0..3 {
+            let mut f = Box::pin(tracer.trace_future(async {
+                trace_label(ENTER);
+                if i > 0 {
+                    core::future::pending::<()>().await;
+                }
+                trace_label(EXIT);
+            }));
+            let is_ready = f.as_mut().poll(&mut cx).is_ready();
+            assert_eq!(is_ready, i == 0);
+        }
+
+        // A future dropped before its first poll never started a trace.
+        drop(tracer.trace_future(async { trace_label(ENTER) }));
+
+        let enter: *const str = ENTER;
+        let exit: *const str = EXIT;
+        assert_eq!(
+            aggregate_ops(&tracer),
+            vec![
+                TraceOpAggregate::FutureStart,
+                TraceOpAggregate::Label { label: enter },
+                TraceOpAggregate::Label { label: exit },
+                TraceOpAggregate::Cancelled,
+            ],
+        );
+        assert_eq!(&tracer.trace_aggregator.counters.borrow()[..], &[2]);
+
+        // Each of the three traces was polled once.
+        let polls = tracer.trace_aggregator.metrics.borrow()[0];
+        assert_eq!((polls.count, polls.sum), (3, 3));
+    }
+
+    #[test]
+    fn test_trace_component_future() {
+        let probius = test_probius();
+        let tracer = TraceSource::new(probius.clone(), "test-component-future", true);
+        let component = Component::new(probius, "test-component", true);
+
+        // The trace around an entered component's future records its polls, each of which runs
+        // in the component.
+        let mut is_woken = false;
+        let f = component.enter_async(core::future::poll_fn(|cx| {
+            if is_woken {
+                return core::task::Poll::Ready(component::current_id());
+            }
+            is_woken = true;
+            cx.waker().wake_by_ref();
+            core::task::Poll::Pending
+        }));
+        let id = pollster::block_on(tracer.trace_future(f));
+        assert_eq!(id, Some(component.id()));
+
+        assert_eq!(aggregate_ops(&tracer), vec![TraceOpAggregate::FutureStart]);
+        let polls = tracer.trace_aggregator.metrics.borrow()[0];
+        assert_eq!((polls.count, polls.sum), (1, 2));
+    }
+
+    #[test]
+    fn test_trace_reentry() {
+        let probius = test_probius();
+        let enter: *const str = ENTER;
+        let exit: *const str = EXIT;
+
+        // With the default depth cap every re-entry collapses into a single call node.
+        let tracer = TraceSource::new(probius.clone(), "test-reentry", true);
+        let call = TraceOpAggregate::Call { source: tracer.source.id() };
+        recurse(&tracer, 5);
+        recurse(&tracer, 2);
+        tracer.trace_aggregator.print();
+        assert_eq!(
+            aggregate_ops(&tracer),
+            vec![
+                TraceOpAggregate::Label { label: enter },
+                call,
+                TraceOpAggregate::Label { label: exit },
+            ],
+        );
+
+        // Levels up to the cap are recorded inline in their own scope.
+        let tracer = TraceSource::new(probius.clone(), "test-reentry-capped", true)
+            .with_max_reentry_depth(1);
+        let call = TraceOpAggregate::Call { source: tracer.source.id() };
+        recurse(&tracer, 5);
+        recurse(&tracer, 2);
+        tracer.trace_aggregator.print();
+        assert_eq!(
+            aggregate_ops(&tracer),
+            vec![
+                TraceOpAggregate::Label { label: enter },
+                call,
+                TraceOpAggregate::PushScope,
+                TraceOpAggregate::Label { label: enter },
+                call,
+                TraceOpAggregate::Label { label: exit },
+                TraceOpAggregate::PopScope,
+                TraceOpAggregate::Label { label: exit },
+            ],
+        );
+
+        // Re-entry from a nested future is treated the same way.
+        let tracer = TraceSource::new(probius, "test-reentry-async", true);
+        let call = TraceOpAggregate::Call { source: tracer.source.id() };
+        pollster::block_on(tracer.trace_future(async {
+            trace_label(ENTER);
+            tracer.trace_future(async { trace_label(ENTER) }).await;
+            trace_label(EXIT);
+        }));
+        assert_eq!(
+            aggregate_ops(&tracer),
+            vec![
+                TraceOpAggregate::FutureStart,
+                TraceOpAggregate::Label { label: enter },
+                call,
+                TraceOpAggregate::Label { label: exit },
+            ],
+        );
+    }
 }
@@ -0,0 +1,219 @@
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use mproto::BaseLen;
+use probius_mproto::{UdpDatagramHeader, UdpDatagramHeaderGen};
+
+use crate::{HandshakeOptions, ProbiusConfig, ProbiusFlusher, Sink, SinkSession};
+
+/// The largest UDP payload that fits in a 1500 byte Ethernet frame over IPv4.
+const DEFAULT_MAX_DATAGRAM_SIZE: usize = 1472;
+
+/// Initialize probius with a UDP sink sending to `remote_addr`. Panics if probius was already
+/// initialized.
+pub fn init_udp_sink(
+    app_name: &str,
+    remote_addr: impl ToSocketAddrs + Send + 'static,
+) -> ProbiusFlusher {
+    let config = ProbiusConfig::new().app_name(app_name).buffer_count(1024);
+    match config.init_with_sink(UdpSink::new(remote_addr)) {
+        Ok(flusher) => flusher,
+        Err(e) => panic!("probius::init_udp_sink: {e}"),
+    }
+}
+
+/// Sends each buffer to a collector as UDP datagrams, never blocking and never retrying.
+///
+/// Every datagram starts with a `UdpDatagramHeader` carrying the session id and a sequence number,
+/// which is 0 for the `SinkHandshake` and counts up from 1 for buffers, so the collector can tell
+/// which buffers were lost. Buffers larger than `max_datagram_size` are split into fragments
+/// which share a sequence number. Use `decode_udp_datagram` to read them.
+///
+/// The handshake is repeated every `handshake_interval`, in case the first one was lost or the
+/// collector was restarted.
+pub struct UdpSink<A> {
+    remote_addr: A,
+    max_datagram_size: usize,
+    handshake_interval: Duration,
+
+    socket: Option<UdpSocket>,
+    session: Option<SinkSession>,
+    sequence: u64,
+    last_handshake: Option<Instant>,
+    fragment_buf: Vec<u8>,
+}
+
+impl<A: ToSocketAddrs + Send + 'static> UdpSink<A> {
+    pub fn new(remote_addr: A) -> Self {
+        Self {
+            remote_addr,
+            max_datagram_size: DEFAULT_MAX_DATAGRAM_SIZE,
+            handshake_interval: Duration::from_secs(1),
+            socket: None,
+            session: None,
+            sequence: 0,
+            last_handshake: None,
+            fragment_buf: Vec::new(),
+        }
+    }
+
+    /// The largest datagram to send, header included. Defaults to 1472 bytes, which fits an
+    /// Ethernet MTU over IPv4.
+    pub fn max_datagram_size(mut self, max_datagram_size: usize) -> Self {
+        self.max_datagram_size = max_datagram_size.max(UdpDatagramHeader::BASE_LEN + 1);
+        self
+    }
+
+    pub fn handshake_interval(mut self, handshake_interval: Duration) -> Self {
+        self.handshake_interval = handshake_interval;
+        self
+    }
+
+    fn send_handshake(&mut self) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let mut datagram = vec![0u8; UdpDatagramHeader::BASE_LEN];
+        datagram.extend_from_slice(&session.handshake(&HandshakeOptions::default()));
+        self.send_fragments(0, &mut datagram);
+        self.last_handshake = Some(Instant::now());
+    }
+
+    /// Send `datagram[BASE_LEN..]` as one or more datagrams with `sequence`. The first `BASE_LEN`
+    /// bytes of `datagram` are overwritten with the header.
+    fn send_fragments(&mut self, sequence: u64, datagram: &mut [u8]) {
+        let (Some(socket), Some(session)) = (&self.socket, &self.session) else {
+            return;
+        };
+        let header = |fragment_index: usize, fragment_count: usize| UdpDatagramHeaderGen {
+            session_id_hi: (session.session_id >> 64) as u64,
+            session_id_lo: session.session_id as u64,
+            sequence,
+            fragment_index: fragment_index as u16,
+            fragment_count: fragment_count as u16,
+        };
+
+        // Dropped datagrams show up as gaps in the sequence, so send errors are ignored.
+        if datagram.len() <= self.max_datagram_size {
+            mproto::encode_value(header(0, 1), &mut datagram[..UdpDatagramHeader::BASE_LEN]);
+            let _ = socket.send(datagram);
+            return;
+        }
+
+        let payload = &datagram[UdpDatagramHeader::BASE_LEN..];
+        let fragment_size = self.max_datagram_size - UdpDatagramHeader::BASE_LEN;
+        let fragment_count = payload.len().div_ceil(fragment_size);
+        for (fragment_index, fragment) in payload.chunks(fragment_size).enumerate() {
+            self.fragment_buf.clear();
+            self.fragment_buf.resize(UdpDatagramHeader::BASE_LEN, 0);
+            let header = header(fragment_index, fragment_count);
+            mproto::encode_value(header, &mut self.fragment_buf[..]);
+            self.fragment_buf.extend_from_slice(fragment);
+            let _ = socket.send(&self.fragment_buf);
+        }
+    }
+}
+
+impl<A: ToSocketAddrs + Send + 'static> Sink for UdpSink<A> {
+    fn headroom(&self) -> usize {
+        UdpDatagramHeader::BASE_LEN
+    }
+
+    fn connect(&mut self, session: &SinkSession) -> io::Result<()> {
+        let remote_addr = self.remote_addr.to_socket_addrs()?
+            .next()
+            .ok_or(io::ErrorKind::AddrNotAvailable)?;
+        let local_addr = match remote_addr {
+            SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+        };
+        let socket = UdpSocket::bind(local_addr)?;
+        socket.connect(remote_addr)?;
+        socket.set_nonblocking(true)?;
+
+        self.socket = Some(socket);
+        self.session = Some(session.clone());
+        self.send_handshake();
+        Ok(())
+    }
+
+    fn send(&mut self, frame: &mut [u8]) -> io::Result<()> {
+        self.sequence += 1;
+        self.send_fragments(self.sequence, frame);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.last_handshake.is_none_or(|last| last.elapsed() >= self.handshake_interval) {
+            self.send_handshake();
+        }
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        self.socket = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{DecodeEventBody, DecodeEvents, DecodeHandshake, decode_udp_datagram};
+
+    #[test]
+    fn test_udp_fragmentation() {
+        let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sink = UdpSink::new(collector.local_addr().unwrap()).max_datagram_size(64);
+        let probius = ProbiusConfig::new().app_name("udp-app").build_with_sink(sink).unwrap();
+
+        drop(probius.new_component("a-component-name-long-enough-to-need-fragments"));
+        probius.shutdown();
+
+        collector.set_nonblocking(true).unwrap();
+        let mut buf = [0u8; 64];
+        let mut handshake_fragments = BTreeMap::new();
+        let mut fragments = BTreeMap::new();
+        while let Ok(len) = collector.recv(&mut buf) {
+            let (header, fragment) = decode_udp_datagram(&buf[..len]).unwrap();
+            if header.sequence == 0 {
+                let value = (header.fragment_count, fragment.to_vec());
+                handshake_fragments.insert(header.fragment_index, value);
+            } else {
+                let key = (header.sequence, header.fragment_index);
+                fragments.insert(key, (header.fragment_count, fragment.to_vec()));
+            }
+        }
+
+        // The handshake doesn't fit in one 64-byte datagram either.
+        let handshake_fragment_count = handshake_fragments.len();
+        assert!(handshake_fragment_count > 1);
+        assert!(handshake_fragments.values().all(|(count, _)| {
+            *count as usize == handshake_fragment_count
+        }));
+        let handshake: Vec<u8> =
+            handshake_fragments.into_values().flat_map(|(_, fragment)| fragment).collect();
+        let handshake = DecodeHandshake::new(&handshake).unwrap();
+        assert_eq!(handshake.session.app_name, "udp-app");
+
+        let fragment_count = fragments.len();
+        assert!(fragment_count > 1);
+        assert!(fragments.iter().all(|((sequence, _), (count, _))| {
+            *sequence == 1 && *count as usize == fragment_count
+        }));
+        let buffer: Vec<u8> = fragments.into_values().flat_map(|(_, fragment)| fragment).collect();
+        let names: Vec<_> = DecodeEvents::new(&buffer)
+            .filter_map(|event| match event.body {
+                DecodeEventBody::CreateSource(create_source) => {
+                    Some(create_source.name().unwrap().to_string())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["a-component-name-long-enough-to-need-fragments"]);
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    io::{self, Write},
+    os::unix::net::{UnixDatagram, UnixStream},
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::{
+    HandshakeOptions, ProbiusConfig, ProbiusFlusher, Sink, SinkSession, sink::read_handshake_reply,
+};
+
+/// Initialize probius with a Unix stream socket sink connected to the collector listening at
+/// `path`. Panics if probius was already initialized.
+pub fn init_unix_sink(app_name: &str, path: impl Into<PathBuf>) -> ProbiusFlusher {
+    let config = ProbiusConfig::new().app_name(app_name).buffer_count(1024);
+    match config.init_with_sink(UnixSink::new(path)) {
+        Ok(flusher) => flusher,
+        Err(e) => panic!("probius::init_unix_sink: {e}"),
+    }
+}
+
+/// Streams buffers to a local collector over a Unix stream socket, framed like `TcpSink`.
+pub struct UnixSink {
+    path: PathBuf,
+    reply_timeout: Option<Duration>,
+    stream: Option<UnixStream>,
+}
+
+impl UnixSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), reply_timeout: None, stream: None }
+    }
+
+    /// Wait up to `reply_timeout` for the collector to reply to the handshake before sending any
+    /// buffers, and disconnect if it doesn't speak this version of the protocol.
+    pub fn negotiate(mut self, reply_timeout: Duration) -> Self {
+        self.reply_timeout = Some(reply_timeout);
+        self
+    }
+}
+
+impl Sink for UnixSink {
+    fn headroom(&self) -> usize {
+        2 // headroom for u16 length prefix
+    }
+
+    fn connect(&mut self, session: &SinkSession) -> io::Result<()> {
+        self.stream = None;
+        let mut stream = UnixStream::connect(&self.path)?;
+        let options = HandshakeOptions {
+            expects_reply: self.reply_timeout.is_some(),
+            ..HandshakeOptions::default()
+        };
+        stream.write_all(&length_prefixed(&session.handshake(&options)))?;
+        if let Some(reply_timeout) = self.reply_timeout {
+            stream.set_read_timeout(Some(reply_timeout))?;
+            read_handshake_reply(&mut stream, options.features())?;
+        }
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn send(&mut self, frame: &mut [u8]) -> io::Result<()> {
+        let Some(stream) = &mut self.stream else {
+            return Err(io::ErrorKind::NotConnected.into());
+        };
+
+        let payload_len = frame.len() - 2;
+        frame[..2].copy_from_slice(&(payload_len as u16).to_le_bytes());
+        if let Err(e) = stream.write_all(frame) {
+            // Reconnected by the sink thread.
+            self.stream = None;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        self.stream = None;
+    }
+}
+
+/// Sends each buffer as one datagram to a local collector's Unix datagram socket, after a
+/// handshake datagram. Framed like `UnixSink` so collectors can decode both with `DecodeFrames`.
+///
+/// Lossy: buffers are dropped instead of waiting when the collector's receive queue is full. If
+/// the collector goes away, the socket is reconnected and the handshake sent again.
+pub struct UnixDatagramSink {
+    path: PathBuf,
+    socket: Option<UnixDatagram>,
+}
+
+impl UnixDatagramSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), socket: None }
+    }
+}
+
+impl Sink for UnixDatagramSink {
+    fn headroom(&self) -> usize {
+        2 // headroom for u16 length prefix
+    }
+
+    fn connect(&mut self, session: &SinkSession) -> io::Result<()> {
+        self.socket = None;
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(&self.path)?;
+        // The handshake is not dropped, since nothing can be decoded without it.
+        socket.send(&length_prefixed(&session.handshake(&HandshakeOptions::default())))?;
+        socket.set_nonblocking(true)?;
+        self.socket = Some(socket);
+        Ok(())
+    }
+
+    fn send(&mut self, frame: &mut [u8]) -> io::Result<()> {
+        let Some(socket) = &self.socket else {
+            return Err(io::ErrorKind::NotConnected.into());
+        };
+
+        let payload_len = frame.len() - 2;
+        frame[..2].copy_from_slice(&(payload_len as u16).to_le_bytes());
+        match socket.send(frame) {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            Err(e) => {
+                self.socket = None;
+                Err(e)
+            }
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.socket.is_some()
+    }
+
+    fn shutdown(&mut self) {
+        self.socket = None;
+    }
+}
+
+fn length_prefixed(payload: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(2 + payload.len());
+    buf.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    buf.extend_from_slice(payload);
+    buf
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Read, os::unix::net::UnixListener, time::Instant};
+
+    use super::*;
+    use crate::{
+        DecodeEventBody, DecodeEvents, DecodeFrames, DecodeHandshake,
+        sink::test::{WAIT_TIMEOUT, watch::SinkWatch},
+    };
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("probius-{name}-{}.sock", fastrand::u64(..)))
+    }
+
+    fn source_names<'a>(frames: impl Iterator<Item = &'a [u8]>) -> Vec<String> {
+        frames
+            .flat_map(DecodeEvents::new)
+            .filter_map(|event| match event.body {
+                DecodeEventBody::CreateSource(create_source) => {
+                    Some(create_source.name().unwrap().to_string())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unix_stream_reconnect() {
+        let path = socket_path("unix-stream");
+        let listener = UnixListener::bind(&path).unwrap();
+        let watch = SinkWatch::default();
+        let probius = ProbiusConfig::new()
+            .app_name("unix-app")
+            .build_with_sink(watch.watch(UnixSink::new(&path)))
+            .unwrap();
+
+        // The collector goes away after the first session.
+        let (mut stream, _) = listener.accept().unwrap();
+        drop(probius.new_component("first"));
+        probius.flush();
+        let mut received = vec![0u8; 2];
+        stream.read_exact(&mut received).unwrap();
+        let handshake_len = u16::from_le_bytes([received[0], received[1]]) as usize;
+        received.resize(2 + handshake_len, 0);
+        stream.read_exact(&mut received[2..]).unwrap();
+        drop(stream);
+
+        // Keep sending until the broken connection is noticed and the sink reconnects.
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        let mut i = 0;
+        while watch.wait_for(|_| true).connects < 2 {
+            assert!(Instant::now() < deadline, "timed out waiting for the sink to reconnect");
+            let sent = watch.wait_for(|_| true).sends;
+            drop(probius.new_component(&format!("retry-{i}")));
+            probius.flush();
+            watch.wait_for(|calls| calls.sends > sent || calls.connects >= 2);
+            i += 1;
+        }
+        let (mut stream, _) = listener.accept().unwrap();
+        drop(probius.new_component("second"));
+        probius.shutdown();
+
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+        let mut frames = DecodeFrames::new(&received);
+        let handshake = DecodeHandshake::new(frames.next().unwrap()).unwrap();
+        assert_eq!(handshake.session.app_name, "unix-app");
+        assert_eq!(source_names(frames.by_ref()).last().map(String::as_str), Some("second"));
+        assert!(frames.remainder().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_unix_datagram() {
+        let path = socket_path("unix-datagram");
+        let collector = UnixDatagram::bind(&path).unwrap();
+        let probius = ProbiusConfig::new()
+            .app_name("unix-app")
+            .build_with_sink(UnixDatagramSink::new(&path))
+            .unwrap();
+
+        drop(probius.new_component("datagram"));
+        probius.shutdown();
+
+        collector.set_nonblocking(true).unwrap();
+        let mut datagrams = Vec::new();
+        let mut buf = vec![0u8; u16::MAX as usize + 2];
+        while let Ok(len) = collector.recv(&mut buf) {
+            datagrams.push(buf[..len].to_vec());
+        }
+
+        let mut frames = datagrams.iter().flat_map(|datagram| DecodeFrames::new(datagram));
+        let handshake = DecodeHandshake::new(frames.next().unwrap()).unwrap();
+        assert_eq!(handshake.session.app_name, "unix-app");
+        assert_eq!(source_names(frames), ["datagram"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}